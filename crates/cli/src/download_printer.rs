@@ -0,0 +1,349 @@
+//! Download printers report the installation progress by "printing" it.
+
+use std::{
+    io::{IsTerminal, Write},
+    path::PathBuf,
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use futures::Sink;
+use humanode_distribution_installer::{progress::Event, size::Bytes};
+
+/// How often to redraw the progress bars.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often to print the progress log lines.
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The width of the progress bars, in characters.
+const BAR_WIDTH: usize = 30;
+
+/// A sink that prints the installation progress to the stderr.
+///
+/// On a terminal the progress is shown as the per-file and the overall
+/// progress bars updated in place, and as periodic log lines otherwise.
+pub struct Stderr {
+    /// Whether to render the progress bars rather than the log lines.
+    live: bool,
+    /// When the installation started.
+    started_at: Instant,
+    /// The number of files to download.
+    files: usize,
+    /// The total size of the files, if known.
+    total_bytes: Option<u64>,
+    /// The number of files downloaded so far.
+    done_files: usize,
+    /// The size of the files downloaded so far.
+    done_bytes: u64,
+    /// The file being downloaded.
+    current: Option<Current>,
+    /// When the progress was last printed.
+    printed_at: Option<Instant>,
+    /// Whether the progress bars are currently on the screen.
+    drawn: bool,
+}
+
+/// The file being downloaded.
+struct Current {
+    /// The local path to the file.
+    path: PathBuf,
+    /// The number of bytes received so far.
+    received: u64,
+    /// The expected size of the file, if known.
+    size: Option<u64>,
+}
+
+impl Stderr {
+    /// Create a new printer, detecting whether the stderr is a terminal.
+    pub fn new() -> Self {
+        Self {
+            live: std::io::stderr().is_terminal(),
+            started_at: Instant::now(),
+            files: 0,
+            total_bytes: None,
+            done_files: 0,
+            done_bytes: 0,
+            current: None,
+            printed_at: None,
+            drawn: false,
+        }
+    }
+
+    /// The number of bytes transferred so far, across all files.
+    fn transferred(&self) -> u64 {
+        self.done_bytes + self.current.as_ref().map_or(0, |current| current.received)
+    }
+
+    /// The overall throughput, in bytes per second.
+    fn throughput(&self) -> f64 {
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.transferred() as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// The overall progress summary: the bytes transferred, the throughput
+    /// and the estimated time left, if the total size is known.
+    fn summary(&self) -> String {
+        let transferred = self.transferred();
+        let throughput = self.throughput();
+        let mut summary = match self.total_bytes {
+            Some(total) => format!("{}/{}", Bytes(transferred), Bytes(total)),
+            None => Bytes(transferred).to_string(),
+        };
+        summary += &format!(
+            " ({}/{} files), {}/s",
+            self.done_files,
+            self.files,
+            Bytes(throughput as u64)
+        );
+        if let Some(total) = self.total_bytes {
+            if throughput > 0.0 {
+                let left = total.saturating_sub(transferred) as f64 / throughput;
+                let left = Duration::from_secs(left.ceil() as u64);
+                summary += &format!(", ETA {}", humantime::format_duration(left));
+            }
+        }
+        summary
+    }
+
+    /// Whether enough time passed since the last print; marks the progress
+    /// printed if so.
+    fn due(&mut self, interval: Duration) -> bool {
+        let now = Instant::now();
+        if self
+            .printed_at
+            .is_some_and(|printed_at| now.duration_since(printed_at) < interval)
+        {
+            return false;
+        }
+        self.printed_at = Some(now);
+        true
+    }
+
+    /// Redraw the progress bars.
+    fn draw(&mut self) {
+        let file_line = match &self.current {
+            Some(Current {
+                path,
+                received,
+                size,
+            }) => match size {
+                Some(size) => format!(
+                    "{} {} {}/{}",
+                    path.display(),
+                    bar(*received, *size),
+                    Bytes(*received),
+                    Bytes(*size)
+                ),
+                None => format!("{} {}", path.display(), Bytes(*received)),
+            },
+            None => String::new(),
+        };
+        let total_line = match self.total_bytes {
+            Some(total) => format!(
+                "Total {} {}",
+                bar(self.transferred(), total),
+                self.summary()
+            ),
+            None => format!("Total {}", self.summary()),
+        };
+
+        let mut stderr = std::io::stderr().lock();
+        if self.drawn {
+            let _ = write!(stderr, "\x1b[1A");
+        }
+        let _ = write!(stderr, "\r\x1b[2K{file_line}\n\r\x1b[2K{total_line}");
+        let _ = stderr.flush();
+        self.drawn = true;
+    }
+
+    /// Leave the progress bars on the screen and move past them.
+    fn finish(&mut self) {
+        if self.drawn {
+            eprintln!();
+            self.drawn = false;
+        }
+    }
+
+    /// Print the event as the progress bars.
+    fn print_live(&mut self, event: Event) {
+        let force = matches!(
+            event,
+            Event::FileStarted { .. } | Event::FileVerified { .. }
+        );
+        self.update(&event);
+        if let Event::PermissionsSet { .. } = event {
+            return;
+        }
+        if force || self.due(REDRAW_INTERVAL) {
+            self.draw();
+        }
+        if self.done_files == self.files && matches!(event, Event::FileVerified { .. }) {
+            self.finish();
+        }
+    }
+
+    /// Print the event as a log line.
+    fn print_log(&mut self, event: Event) {
+        self.update(&event);
+        match event {
+            Event::Started { files, total_bytes } => match total_bytes {
+                Some(total_bytes) => {
+                    eprintln!("Downloading {files} files, {} in total", Bytes(total_bytes))
+                }
+                None => eprintln!("Downloading {files} files"),
+            },
+            Event::FileStarted { path, url, .. } => {
+                eprintln!("Downloading {url} to {}", path.display())
+            }
+            Event::FileProgress {
+                path,
+                received,
+                size,
+            } => {
+                if self.due(LOG_INTERVAL) {
+                    let received = match size {
+                        Some(size) => format!("{}/{}", Bytes(received), Bytes(size)),
+                        None => Bytes(received).to_string(),
+                    };
+                    eprintln!(
+                        "Downloading {}: {received}; total {}",
+                        path.display(),
+                        self.summary()
+                    );
+                }
+            }
+            Event::FileVerified {
+                path,
+                size,
+                duration,
+            } => eprintln!(
+                "Downloaded and verified {}: {} in {duration:.2?}",
+                path.display(),
+                Bytes(size)
+            ),
+            Event::PermissionsSet { path } => {
+                eprintln!("Set executable permissions for {}", path.display())
+            }
+        }
+    }
+
+    /// Account the event in the progress state.
+    ///
+    /// The same printer is used for every package of a composite
+    /// installation, so the progress starts over for each package.
+    fn update(&mut self, event: &Event) {
+        match event {
+            Event::Started { files, total_bytes } => {
+                self.started_at = Instant::now();
+                self.files = *files;
+                self.total_bytes = *total_bytes;
+                self.done_files = 0;
+                self.done_bytes = 0;
+                self.current = None;
+            }
+            Event::FileStarted { path, size, .. } => {
+                self.current = Some(Current {
+                    path: path.clone(),
+                    received: 0,
+                    size: *size,
+                });
+            }
+            Event::FileProgress { received, .. } => {
+                if let Some(current) = &mut self.current {
+                    current.received = *received;
+                }
+            }
+            Event::FileVerified { size, .. } => {
+                self.current = None;
+                self.done_files += 1;
+                self.done_bytes += size;
+            }
+            Event::PermissionsSet { .. } => {}
+        }
+    }
+}
+
+impl Default for Stderr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Stderr {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl Sink<Event> for Stderr {
+    type Error = ();
+
+    fn poll_ready(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: std::pin::Pin<&mut Self>, item: Event) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        if this.live {
+            this.print_live(item);
+        } else {
+            this.print_log(item);
+        }
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Render a progress bar.
+fn bar(done: u64, total: u64) -> String {
+    let filled = if total == 0 {
+        BAR_WIDTH
+    } else {
+        (done.min(total) as f64 / total as f64 * BAR_WIDTH as f64) as usize
+    };
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_over_for_every_package() {
+        let mut printer = Stderr::new();
+        for size in [10, 20] {
+            printer.update(&Event::Started {
+                files: 1,
+                total_bytes: Some(size),
+            });
+            printer.update(&Event::FileVerified {
+                path: "run".into(),
+                size,
+                duration: Duration::ZERO,
+            });
+            assert_eq!(printer.done_files, 1);
+            assert_eq!(printer.transferred(), size);
+        }
+    }
+}
@@ -22,8 +22,7 @@ impl Sink<ResolutionError> for Stderr {
         self: std::pin::Pin<&mut Self>,
         item: ResolutionError,
     ) -> Result<(), Self::Error> {
-        let ResolutionError { url, error } = item;
-        eprintln!("An error occurred during resolution at {url}: {error}");
+        eprintln!("An error occurred during resolution at {item}");
         Ok(())
     }
 
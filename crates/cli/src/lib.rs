@@ -1,5 +1,9 @@
 //! The CLI support library.
 
+pub mod download_printer;
 pub mod issue_printer;
+pub mod output;
 pub mod package_render;
+pub mod picker;
+pub mod progress_printer;
 pub mod selector;
@@ -3,18 +3,44 @@
 #![allow(missing_docs)]
 #![allow(clippy::missing_docs_in_private_items)]
 
-use std::process::ExitCode;
+use std::{
+    collections::{BTreeMap, HashSet},
+    num::NonZeroU64,
+    process::ExitCode,
+    time::Duration,
+};
 
 use clap::{Args, Parser, Subcommand};
+use futures::{future::Either, SinkExt};
+use humanode_distribution::output::{self, Document, ErrorKind, Format, PackageEntry};
 use humanode_distribution_config::load::SourcesLoadingResult;
-use humanode_distribution_resolver::resolve::Contextualized;
+use humanode_distribution_resolver::{
+    filter::{expr::Expr, Fit, Support},
+    resolve::Contextualized,
+};
 use humanode_distribution_schema::manifest::Package;
 
+/// The exit codes, as documented in the help.
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  Success
+  1  Unclassified failure
+  2  Invalid command line usage
+  3  The packages or their dependencies could not be resolved
+  4  No package could be selected
+  5  The system does not have the resources the package needs
+  6  The installation failed";
+
 #[derive(Debug, Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = EXIT_CODES)]
 struct Cli {
     #[command(subcommand)]
     command: Command,
+
+    /// The output format; with `json`, every command prints a single JSON
+    /// document to the stdout, including on failure.
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    output: Format,
 }
 
 #[derive(Debug, Subcommand)]
@@ -25,6 +51,9 @@ enum Command {
     Eval(Eval),
     /// Install the distribution into a given directory.
     Install(Install),
+    /// Update the package installed into a given directory to its newest
+    /// version.
+    Update(Update),
     /// Display the sources.
     Sources(Sources),
 }
@@ -39,11 +68,12 @@ struct SourcesArgs {
     #[arg(long, default_value_t = false)]
     no_config_files: bool,
 
-    /// The list of URLs to fetch the repos from.
+    /// The list of URLs to fetch the repos from; local paths are accepted too.
     #[arg(short, long)]
     repo_urls: Vec<String>,
 
-    /// The list of URLs to fetch the manifests from; in addition to repos.
+    /// The list of URLs to fetch the manifests from; in addition to repos;
+    /// local paths are accepted too.
     #[arg(short, long)]
     manifest_urls: Vec<String>,
 }
@@ -60,19 +90,82 @@ struct ResolutionArgs {
     /// The arch to use, current system's arch will be used by default.
     #[arg(short, long)]
     arch: Option<String>,
+
+    /// Do not access the network, resolve from the cached repos and manifests only.
+    #[arg(long, default_value_t = false)]
+    offline: bool,
+
+    /// Fail if any of the repos or manifests can't be loaded, instead of
+    /// resolving from the rest.
+    #[arg(long, default_value_t = false)]
+    strict: bool,
+
+    /// The expression to select the packages by, in addition to the platform
+    /// and arch, like `name ~ "Humanode*" && description contains node`;
+    /// the fields are id, name, version, channel, description, manifest,
+    /// platform and arch, and the version is also compared with <, <=, >
+    /// and >=.
+    #[arg(long = "where", value_name = "EXPR")]
+    where_expr: Option<Expr>,
+}
+
+#[derive(Debug, Args)]
+struct NetworkArgs {
+    /// The maximum number of attempts for each network request, including
+    /// the first one.
+    #[arg(long)]
+    max_attempts: Option<u32>,
+
+    /// The delay before the first retry of a failed network request;
+    /// doubles with every next retry.
+    #[arg(long, value_parser = humantime::parse_duration)]
+    retry_backoff: Option<Duration>,
+
+    /// The upper bound for the delay between the retries, including
+    /// the delays the servers ask for.
+    #[arg(long, value_parser = humantime::parse_duration)]
+    retry_max_backoff: Option<Duration>,
+}
+
+#[derive(Debug, Args)]
+struct ExplainArgs {
+    /// Report every package found along with the reasons it was filtered out
+    /// for, grouped by manifest.
+    #[arg(long, default_value_t = false)]
+    explain: bool,
 }
 
 #[derive(Debug, Args)]
 struct SelectionArgs {
-    /// The package display name to select.
+    /// The identifier of the package to select.
+    #[arg(long)]
+    package_id: Option<String>,
+
+    /// The package display name to select; matched exactly if possible, and
+    /// then regardless of the case or partially.
     #[arg(long)]
     package_display_name: Option<String>,
+
+    /// The URL of the manifest to select the package from.
+    #[arg(long)]
+    package_manifest_url: Option<String>,
+
+    /// The 1-based position of the package to select in the `list` output
+    /// for the same resolution args.
+    #[arg(long, conflicts_with_all = ["package_id", "package_display_name", "package_manifest_url"])]
+    package_index: Option<usize>,
 }
 
 #[derive(Debug, Args)]
 struct RenderingArgs {
     #[arg(long, default_value = "display-name")]
     renderer: humanode_distribution::package_render::Renderer,
+
+    /// Render each package with the template instead, like
+    /// `{displayName}\t{manifestUrl}`; the fields are id, displayName,
+    /// version, description, platform, arch, support and manifestUrl.
+    #[arg(long, value_name = "TEMPLATE")]
+    format: Option<humanode_distribution::package_render::Template>,
 }
 
 #[derive(Debug, Parser)]
@@ -80,6 +173,12 @@ struct List {
     #[clap(flatten)]
     resolution_args: ResolutionArgs,
 
+    #[clap(flatten)]
+    network_args: NetworkArgs,
+
+    #[clap(flatten)]
+    explain_args: ExplainArgs,
+
     #[clap(flatten)]
     rendering_args: RenderingArgs,
 }
@@ -89,6 +188,12 @@ struct Eval {
     #[clap(flatten)]
     resolution_args: ResolutionArgs,
 
+    #[clap(flatten)]
+    network_args: NetworkArgs,
+
+    #[clap(flatten)]
+    explain_args: ExplainArgs,
+
     #[clap(flatten)]
     selection_args: SelectionArgs,
 
@@ -101,12 +206,42 @@ struct Install {
     #[clap(flatten)]
     resolution_args: ResolutionArgs,
 
+    #[clap(flatten)]
+    network_args: NetworkArgs,
+
     #[clap(flatten)]
     selection_args: SelectionArgs,
 
+    #[clap(flatten)]
+    installation_args: InstallationArgs,
+}
+
+#[derive(Debug, Parser)]
+struct Update {
+    #[clap(flatten)]
+    resolution_args: ResolutionArgs,
+
+    #[clap(flatten)]
+    network_args: NetworkArgs,
+
+    #[clap(flatten)]
+    installation_args: InstallationArgs,
+}
+
+#[derive(Debug, Args)]
+struct InstallationArgs {
     /// The directory to install to.
     #[arg(short, long, default_value = ".")]
     dir: String,
+
+    /// Install even if the system does not have the resources the package needs.
+    #[arg(long, default_value_t = false)]
+    force: bool,
+
+    /// The maximum download rate in bytes per second, shared by all
+    /// the downloads; accepts the K, M and G suffixes, like `5M`.
+    #[arg(long, value_parser = parse_rate)]
+    limit_rate: Option<NonZeroU64>,
 }
 
 #[derive(Debug, Parser)]
@@ -119,21 +254,101 @@ struct Sources {
 async fn main() -> ExitCode {
     tracing_subscriber::fmt::init();
     color_eyre::install().unwrap();
-    let cli = Cli::parse();
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(error) => return usage_error(error),
+    };
 
+    let mut context = Context {
+        output: cli.output,
+        issues: Vec::new(),
+    };
     let result = match cli.command {
-        Command::List(args) => list(args).await,
-        Command::Eval(args) => eval(args).await,
-        Command::Install(args) => install(args).await,
-        Command::Sources(args) => sources(args).await,
+        Command::List(args) => list(args, &mut context).await,
+        Command::Eval(args) => eval(args, &mut context).await,
+        Command::Install(args) => install(args, &mut context).await,
+        Command::Update(args) => update(args, &mut context).await,
+        Command::Sources(args) => sources(args, &mut context).await,
+    };
+
+    let Err(error) = result else {
+        return ExitCode::SUCCESS;
     };
 
-    if let Err(error) = result {
-        eprintln!("Error: {error:?}");
-        return ExitCode::FAILURE;
+    match context.output {
+        Format::Text => eprintln!("Error: {error:?}"),
+        Format::Json => {
+            let document = Document::<()> {
+                version: output::VERSION,
+                result: None,
+                error: Some((&error).into()),
+                issues: context.issues,
+            };
+            if let Err(print_error) = document.print() {
+                eprintln!("Error: {error:?}\nUnable to print the error: {print_error}");
+            }
+        }
     }
+    ExitCode::from(ErrorKind::classify(&error).exit_code())
+}
+
+/// Report the command line parsing error in the requested format.
+fn usage_error(error: clap::Error) -> ExitCode {
+    use clap::error::ErrorKind as ClapErrorKind;
 
-    ExitCode::SUCCESS
+    // The help and the version are printed as usual.
+    let is_failure = !matches!(
+        error.kind(),
+        ClapErrorKind::DisplayHelp | ClapErrorKind::DisplayVersion
+    );
+    if !is_failure || Format::requested(std::env::args_os().skip(1)) == Format::Text {
+        error.exit();
+    }
+
+    let document = Document::<()> {
+        version: output::VERSION,
+        result: None,
+        error: Some((&error).into()),
+        issues: Vec::new(),
+    };
+    if let Err(print_error) = document.print() {
+        eprintln!("Error: {error}\nUnable to print the error: {print_error}");
+    }
+    ExitCode::from(ErrorKind::Usage.exit_code())
+}
+
+/// The state shared across the command run.
+struct Context {
+    /// The output format.
+    output: Format,
+    /// The resolution issues, collected for the JSON output.
+    issues: Vec<output::Issue>,
+}
+
+impl Context {
+    /// Whether the user can be asked questions: the output is meant for
+    /// humans and both the stdin and the stderr are terminals.
+    fn is_interactive(&self) -> bool {
+        use std::io::IsTerminal;
+
+        self.output == Format::Text
+            && std::io::stdin().is_terminal()
+            && std::io::stderr().is_terminal()
+    }
+}
+
+impl Context {
+    /// Print the command result as a JSON document, along with the issues.
+    fn emit(&mut self, result: impl serde::Serialize) -> Result<(), eyre::Error> {
+        let document = Document {
+            version: output::VERSION,
+            result: Some(result),
+            error: None,
+            issues: std::mem::take(&mut self.issues),
+        };
+        document.print()?;
+        Ok(())
+    }
 }
 
 fn add_built_in_sources(sources: &mut humanode_distribution_config::Sources) {
@@ -172,9 +387,70 @@ async fn load_configs(all_sources: &mut humanode_distribution_config::Sources) {
     }
 }
 
+// Load the settings from the configs, print encountered errors.
+async fn load_settings() -> humanode_distribution_config::Settings {
+    let mut all_settings = humanode_distribution_config::Settings::default();
+    let config_paths = humanode_distribution_config::paths::configs();
+    for config_path in config_paths {
+        match humanode_distribution_config::load::settings(config_path).await {
+            Ok(settings) => all_settings.extend(settings),
+            Err(error) => eprintln!("Loading the config files: {}", error),
+        }
+    }
+    all_settings
+}
+
+/// The network facilities shared by the resolution and the installation.
+struct Network {
+    /// The transport.
+    transport: humanode_distribution_transport::standard::Standard,
+    /// The retry policy.
+    retry_policy: humanode_distribution_transport::retry::Policy,
+    /// The configured maximum download rate, in bytes per second.
+    max_bytes_per_second: Option<NonZeroU64>,
+}
+
+/// Common CLI logic to process the network args and apply the settings from
+/// the configs.
+async fn prepare_network(network_args: NetworkArgs, no_config_files: bool) -> Network {
+    let NetworkArgs {
+        max_attempts,
+        retry_backoff,
+        retry_max_backoff,
+    } = network_args;
+
+    let humanode_distribution_config::Settings { retry, download } = if no_config_files {
+        Default::default()
+    } else {
+        load_settings().await
+    };
+
+    let mut retry_policy = humanode_distribution_transport::retry::Policy::default();
+    if let Some(max_attempts) = max_attempts.or(retry.max_attempts) {
+        retry_policy.max_attempts = max_attempts.max(1);
+    }
+    if let Some(initial_backoff) =
+        retry_backoff.or(retry.initial_backoff_ms.map(Duration::from_millis))
+    {
+        retry_policy.initial_backoff = initial_backoff;
+    }
+    if let Some(max_backoff) = retry_max_backoff.or(retry.max_backoff_ms.map(Duration::from_millis))
+    {
+        retry_policy.max_backoff = max_backoff;
+    }
+
+    Network {
+        transport: humanode_distribution_transport::standard::Standard::new(reqwest::Client::new()),
+        retry_policy,
+        max_bytes_per_second: download.max_bytes_per_second,
+    }
+}
+
 /// Common CLI logic to process the source args and load the sources from
 /// the configs.
-async fn prepare_sources(sources_args: SourcesArgs) -> humanode_distribution_config::Sources {
+async fn prepare_sources(
+    sources_args: SourcesArgs,
+) -> Result<humanode_distribution_config::Sources, eyre::Error> {
     let SourcesArgs {
         no_built_in_sources,
         no_config_files,
@@ -192,136 +468,717 @@ async fn prepare_sources(sources_args: SourcesArgs) -> humanode_distribution_con
         load_configs(&mut sources).await;
     }
 
-    sources.repo_urls.extend(repo_urls);
-    sources.manifest_urls.extend(manifest_urls);
+    let source_urls = |values: Vec<String>| {
+        values
+            .iter()
+            .map(|value| humanode_distribution_transport::file::source_url(value))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    sources.repo_urls.extend(source_urls(repo_urls)?);
+    sources.manifest_urls.extend(source_urls(manifest_urls)?);
+
+    Ok(sources)
+}
+
+/// A package that matches the filter.
+#[derive(Clone)]
+struct Candidate {
+    /// The package.
+    package: Contextualized<Package>,
+    /// How well the package suits the system.
+    fit: Fit,
+}
+
+impl AsRef<Package> for Candidate {
+    fn as_ref(&self) -> &Package {
+        &self.package.value
+    }
+}
 
-    sources
+impl AsRef<Contextualized<Package>> for Candidate {
+    fn as_ref(&self) -> &Contextualized<Package> {
+        &self.package
+    }
+}
+
+/// The packages that suit the system.
+struct Resolved {
+    /// The packages that also match the `--where` expression, to select
+    /// from.
+    candidates: Vec<Candidate>,
+    /// All the packages, to pick the dependencies from, as the `--where`
+    /// expression only narrows the selection.
+    available: Vec<Candidate>,
 }
 
 /// Common CLI logic to run the resolver from the given args.
+///
+/// The packages are ranked, with the native packages going first, and
+/// the newer versions going first among the equally fitting ones, so that
+/// the newest version of a package is selected by default.
+/// With `explain` set, every package found is reported along with
+/// the reasons it was filtered out for.
+///
+/// With the JSON output the issues are collected into the context rather
+/// than printed.
 async fn resolve(
     resolution_args: ResolutionArgs,
-) -> Result<Vec<Contextualized<Package>>, eyre::Error> {
+    network: &Network,
+    explain: bool,
+    context: &mut Context,
+) -> Result<Resolved, eyre::Error> {
     let ResolutionArgs {
         sources_args,
         platform,
         arch,
+        offline,
+        strict,
+        where_expr,
     } = resolution_args;
 
     let humanode_distribution_config::Sources {
         manifest_urls,
         repo_urls,
-    } = prepare_sources(sources_args).await;
-
-    // Detect platform and arch if not specified.
-    let (platform, arch) = match (platform, arch) {
-        (Some(platform), Some(arch)) => (platform, arch),
-        (platform, arch) => {
-            let detected = humanode_distribution_detection::detect()?;
-            (
-                platform.unwrap_or(detected.platform),
-                arch.unwrap_or(detected.arch),
-            )
-        }
-    };
+    } = prepare_sources(sources_args).await?;
 
-    let client = reqwest::Client::new();
+    // Detect the system if the platform or arch is not specified.
+    let detected = match (&platform, &arch) {
+        (Some(_), Some(_)) => None,
+        _ => Some(humanode_distribution_detection::detect()?),
+    };
+    let filter = filter_params(platform, arch, detected);
 
-    let filter = humanode_distribution_resolver::filter::Params { platform, arch };
+    let cache = humanode_distribution_config::paths::cache()
+        .map(humanode_distribution_resolver::cache::Cache::new);
+    let mode = if offline {
+        humanode_distribution_resolver::resolve::Mode::Offline
+    } else {
+        humanode_distribution_resolver::resolve::Mode::Online
+    };
 
-    let packages = humanode_distribution_resolver::resolve::resolve(
-        client,
+    // With `--explain`, every package found is kept aside to report
+    // the reasons the filtered out ones are rejected for.
+    let mut unfiltered = Vec::new();
+    let mut issues = Vec::new();
+    let issues_sink = match context.output {
+        Format::Text => Either::Left(humanode_distribution::issue_printer::Stderr),
+        Format::Json => Either::Right((&mut issues).sink_map_err(|_| ())),
+    };
+    let result = humanode_distribution_resolver::resolve::resolve(
+        &network.transport,
         humanode_distribution_resolver::resolve::Params {
             manifest_urls,
             repo_urls,
+            cache,
+            mode,
+            strict,
+            retry_policy: network.retry_policy.clone(),
+        },
+        issues_sink,
+        humanode_distribution::progress_printer::Stderr::new(),
+        |package| {
+            if explain {
+                unfiltered.push(package.clone());
+            }
+            filter.matches(package)
         },
-        humanode_distribution::issue_printer::Stderr,
-        |package| filter.matches(package),
     )
     .await;
+    context
+        .issues
+        .extend(issues.iter().map(output::Issue::from));
+    let packages = result?;
+
+    if explain {
+        report_rejections(&filter, where_expr.as_ref(), &unfiltered);
+    }
+
+    let mut available: Vec<Candidate> = packages
+        .into_iter()
+        .filter_map(|package| {
+            let fit = filter.check(&package).ok()?;
+            Some(Candidate { package, fit })
+        })
+        .collect();
+    // The newer versions go first among the equally fitting packages, and
+    // the ones without a version go last.
+    available.sort_by(|a, b| {
+        let version = |candidate: &Candidate| {
+            candidate
+                .package
+                .value
+                .version
+                .as_ref()
+                .map(|version| version.0.clone())
+        };
+        a.fit
+            .rank()
+            .cmp(&b.fit.rank())
+            .then_with(|| match (version(a), version(b)) {
+                (Some(a), Some(b)) => humanode_distribution_resolver::version::compare(&b, &a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            })
+    });
+
+    let candidates: Vec<Candidate> = available
+        .iter()
+        .filter(|candidate| matches_expr(&where_expr, &candidate.package))
+        .cloned()
+        .collect();
+
+    Ok(Resolved {
+        candidates,
+        available,
+    })
+}
+
+/// Build the filter params from the specified platform and arch and
+/// the detected system info.
+///
+/// The translation is only considered for the detected arch, and the rest of
+/// the system details only for the detected platform, as they don't apply to
+/// the other targets.
+fn filter_params(
+    platform: Option<String>,
+    arch: Option<String>,
+    detected: Option<humanode_distribution_detection::Info>,
+) -> humanode_distribution_resolver::filter::Params {
+    let Some(detected) = detected else {
+        return humanode_distribution_resolver::filter::Params {
+            platform: platform.unwrap_or_default(),
+            arch: arch.unwrap_or_default(),
+            translated_arches: Vec::new(),
+            os_version: None,
+            distribution: None,
+            kernel_release: None,
+            libc: None,
+            cpu_features: Vec::new(),
+        };
+    };
+
+    let humanode_distribution_detection::Info {
+        arch: detected_arch,
+        platform: detected_platform,
+        translated_arches,
+        cpu_features,
+        kernel_release,
+        os_version,
+        distribution,
+        libc,
+        memory: _,
+        cpu_cores: _,
+    } = detected;
+
+    // The detected CPU only tells about the requested arch if it is
+    // the detected one.
+    let is_other_arch = arch.as_ref().is_some_and(|arch| {
+        humanode_distribution_platform::arch(arch)
+            != humanode_distribution_platform::arch(&detected_arch)
+    });
+    let (translated_arches, cpu_features) = if is_other_arch {
+        (Vec::new(), Vec::new())
+    } else {
+        (translated_arches, cpu_features)
+    };
+
+    let (os_version, distribution, kernel_release, libc) = if platform.is_some() {
+        (None, None, None, None)
+    } else {
+        let libc = libc.map(|libc| humanode_distribution_resolver::filter::Libc {
+            kind: libc.kind,
+            version: libc.version,
+        });
+        let distribution = distribution.map(|distribution| distribution.id);
+        (os_version, distribution, kernel_release, libc)
+    };
+
+    humanode_distribution_resolver::filter::Params {
+        platform: platform.unwrap_or(detected_platform),
+        arch: arch.unwrap_or(detected_arch),
+        translated_arches,
+        os_version,
+        distribution,
+        kernel_release,
+        libc,
+        cpu_features,
+    }
+}
 
-    Ok(packages)
+/// Check if the package matches the `--where` expression, if any.
+fn matches_expr(where_expr: &Option<Expr>, package: &Contextualized<Package>) -> bool {
+    where_expr.as_ref().is_none_or(|expr| expr.matches(package))
 }
 
+/// Report every package along with the reasons the filter and the `--where`
+/// expression reject it for, grouped by manifest.
+fn report_rejections(
+    filter: &humanode_distribution_resolver::filter::Params,
+    where_expr: Option<&Expr>,
+    packages: &[Contextualized<Package>],
+) {
+    let mut by_manifest: BTreeMap<&str, Vec<&Contextualized<Package>>> = BTreeMap::new();
+    for package in packages {
+        by_manifest
+            .entry(package.manifest_url.as_str())
+            .or_default()
+            .push(package);
+    }
+
+    for (manifest_url, packages) in by_manifest {
+        eprintln!("Manifest {manifest_url}:");
+        for package in packages {
+            let Package {
+                display_name,
+                platform,
+                arch,
+                ..
+            } = &package.value;
+            let (support, mut reasons) = match filter.check(package) {
+                Ok(Fit { support, .. }) => (Some(support), Vec::new()),
+                Err(rejections) => (None, rejections.iter().map(ToString::to_string).collect()),
+            };
+            if where_expr.is_some_and(|expr| !expr.matches(package)) {
+                reasons.push("the --where expression does not match".to_owned());
+            }
+            let verdict = match support {
+                _ if !reasons.is_empty() => format!("filtered out: {}", reasons.join(", ")),
+                Some(Support::Emulated) => "matches via binary translation".to_owned(),
+                _ => "matches".to_owned(),
+            };
+            eprintln!("  {display_name} ({} {}): {verdict}", platform.0, arch.0);
+        }
+    }
+}
+
+/// Select the package to use.
+///
+/// With `interactive` set, the user is asked to pick one if more than one
+/// package is available and none is named.
 fn select(
     args: SelectionArgs,
-    packages: Vec<Contextualized<Package>>,
-) -> Result<Contextualized<Package>, eyre::Error> {
+    candidates: &[Candidate],
+    interactive: bool,
+) -> Result<&Candidate, eyre::Error> {
     let SelectionArgs {
+        package_id,
         package_display_name,
+        package_manifest_url,
+        package_index,
     } = args;
 
-    let selector = humanode_distribution::selector::Selector {
-        package_display_name,
+    // The index refers to the `list` output, so select from the full list.
+    if package_index.is_some() {
+        let selector = humanode_distribution::selector::Selector {
+            index: package_index,
+            ..Default::default()
+        };
+        return Ok(selector.select(candidates.iter().collect())?);
+    }
+
+    // The builds of the same package in a manifest share the identifier, or
+    // the display name if there is none, so only keep the best fitting one
+    // for each; the candidates are ranked already. The packages of different
+    // manifests are kept apart, as they are not necessarily the same.
+    let mut seen = HashSet::new();
+    let candidates: Vec<_> = candidates
+        .iter()
+        .filter(|candidate| {
+            package_manifest_url
+                .as_ref()
+                .is_none_or(|url| &candidate.package.manifest_url == url)
+        })
+        .filter(|candidate| {
+            let package = &candidate.package.value;
+            let key = match package.id {
+                Some(ref id) => (true, id.0.clone()),
+                None => (false, package.display_name.clone()),
+            };
+            let key = (candidate.package.manifest_url.clone(), key);
+            seen.insert(key)
+        })
+        .collect();
+
+    // Fall back to the emulated packages only if there are no native ones,
+    // unless the package is named explicitly.
+    let best = candidates
+        .iter()
+        .map(|candidate| candidate.fit.support)
+        .min();
+    let candidates: Vec<_> = candidates
+        .into_iter()
+        .filter(|candidate| {
+            package_id.is_some()
+                || package_display_name.is_some()
+                || Some(candidate.fit.support) == best
+        })
+        .collect();
+
+    let selected = if interactive
+        && package_id.is_none()
+        && package_display_name.is_none()
+        && package_manifest_url.is_none()
+        && candidates.len() > 1
+    {
+        humanode_distribution::picker::pick(
+            candidates,
+            |candidate| &candidate.package.manifest_url,
+            std::io::stdin().lock(),
+            std::io::stderr(),
+        )?
+    } else {
+        let selector = humanode_distribution::selector::Selector {
+            package_id,
+            package_display_name,
+            manifest_url: package_manifest_url,
+            index: None,
+        };
+        selector.select(candidates)?
     };
-    let selected = selector.select(packages)?;
+
+    if selected.fit.support == Support::Emulated {
+        eprintln!(
+            "Warning: {:?} is not native to this system and will run via binary translation",
+            selected.package.value.display_name
+        );
+    }
 
     Ok(selected)
 }
 
 /// List command.
-async fn list(args: List) -> Result<(), eyre::Error> {
+async fn list(args: List, context: &mut Context) -> Result<(), eyre::Error> {
     let List {
         resolution_args,
+        network_args,
+        explain_args,
         rendering_args,
     } = args;
-    let RenderingArgs { renderer } = rendering_args;
-    let packages = resolve(resolution_args).await?;
+    let RenderingArgs { renderer, format } = rendering_args;
+    let ExplainArgs { explain } = explain_args;
+    let network = prepare_network(network_args, resolution_args.sources_args.no_config_files).await;
+    let Resolved { candidates, .. } = resolve(resolution_args, &network, explain, context).await?;
 
-    for package in packages {
-        println!("{}", renderer.render_to_string(&package.value)?);
+    if context.output == Format::Json {
+        let entries: Vec<_> = candidates
+            .iter()
+            .map(|candidate| PackageEntry::new(&candidate.package, candidate.fit.support))
+            .collect();
+        return context.emit(entries);
+    }
+
+    if let Some(template) = format {
+        for candidate in candidates {
+            println!(
+                "{}",
+                template.render(&candidate.package, candidate.fit.support)
+            );
+        }
+        return Ok(());
+    }
+
+    if let humanode_distribution::package_render::Renderer::Table = renderer {
+        let packages: Vec<_> = candidates
+            .iter()
+            .map(|candidate| (&candidate.package, candidate.fit.support))
+            .collect();
+        humanode_distribution::package_render::render_table(std::io::stdout().lock(), &packages)?;
+        println!();
+        return Ok(());
+    }
+
+    for candidate in candidates {
+        let rendered = renderer.render_to_string(&candidate.package, candidate.fit.support)?;
+        println!("{rendered}");
     }
 
     Ok(())
 }
 
 /// Eval command.
-async fn eval(args: Eval) -> Result<(), eyre::Error> {
+async fn eval(args: Eval, context: &mut Context) -> Result<(), eyre::Error> {
     let Eval {
         resolution_args,
+        network_args,
+        explain_args,
         selection_args,
         rendering_args,
     } = args;
-    let RenderingArgs { renderer } = rendering_args;
-    let packages = resolve(resolution_args).await?;
-    let selected = select(selection_args, packages)?;
-    println!("{}", renderer.render_to_string(&selected.value)?);
+    let RenderingArgs { renderer, format } = rendering_args;
+    let ExplainArgs { explain } = explain_args;
+    let network = prepare_network(network_args, resolution_args.sources_args.no_config_files).await;
+    let Resolved { candidates, .. } = resolve(resolution_args, &network, explain, context).await?;
+    let Candidate { package, fit } = select(selection_args, &candidates, context.is_interactive())?;
+    if context.output == Format::Json {
+        return context.emit(PackageEntry::new(package, fit.support));
+    }
+    let rendered = match format {
+        Some(template) => template.render(package, fit.support),
+        None => renderer.render_to_string(package, fit.support)?,
+    };
+    println!("{rendered}");
     Ok(())
 }
 
 /// Install command.
-async fn install(args: Install) -> Result<(), eyre::Error> {
+async fn install(args: Install, context: &mut Context) -> Result<(), eyre::Error> {
     let Install {
         resolution_args,
+        network_args,
         selection_args,
-        dir,
+        installation_args,
     } = args;
-    let packages = resolve(resolution_args).await?;
-    let selected = select(selection_args, packages)?;
+    let network = prepare_network(network_args, resolution_args.sources_args.no_config_files).await;
+    let Resolved {
+        candidates,
+        available,
+    } = resolve(resolution_args, &network, false, context).await?;
+    let selected = select(selection_args, &candidates, context.is_interactive())?;
 
-    println!(
-        "Installing {:?} to {:?}...",
-        selected.value.display_name, dir
-    );
+    install_selected(
+        selected,
+        &available,
+        installation_args,
+        network,
+        context,
+        |context, result| context.emit(result),
+    )
+    .await
+}
+
+/// Update command.
+async fn update(args: Update, context: &mut Context) -> Result<(), eyre::Error> {
+    let Update {
+        resolution_args,
+        network_args,
+        installation_args,
+    } = args;
+
+    let dir = std::path::Path::new(&installation_args.dir);
+    let Some(receipt) = humanode_distribution_installer::receipt::read(dir).await? else {
+        eyre::bail!("no package is installed at {dir:?}");
+    };
+    let Some(id) = receipt.id else {
+        eyre::bail!(
+            "{:?} installed at {dir:?} has no identifier, so it can not be updated",
+            receipt.display_name
+        );
+    };
 
-    let client = reqwest::Client::new();
+    let network = prepare_network(network_args, resolution_args.sources_args.no_config_files).await;
+    let Resolved {
+        candidates,
+        available,
+    } = resolve(resolution_args, &network, false, context).await?;
+
+    // The candidates are ranked by the fit and then by the version, so
+    // the first one is the best fitting newest build of the package.
+    let selected = candidates
+        .iter()
+        .find(|candidate| candidate.package.value.id.as_ref() == Some(&id))
+        .ok_or(humanode_distribution::selector::SelectionError::NotFound {
+            suggestions: vec![id.to_string()],
+        })?;
+
+    let installed_version = receipt.version.map(|version| version.0);
+    let available_version = selected
+        .package
+        .value
+        .version
+        .as_ref()
+        .map(|version| version.0.as_str());
+    let is_newer = match (available_version, installed_version.as_deref()) {
+        (Some(available), Some(installed)) => {
+            humanode_distribution_resolver::version::compare(available, installed).is_gt()
+        }
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    let describe = |version: Option<&str>| version.unwrap_or("an unversioned build").to_owned();
+
+    if !is_newer {
+        if context.output == Format::Json {
+            return context.emit(output::UpdateResult {
+                previous_version: installed_version.as_deref(),
+                installed: None,
+            });
+        }
+        println!(
+            "{id} is up to date at {}",
+            describe(installed_version.as_deref())
+        );
+        return Ok(());
+    }
+
+    if context.output == Format::Text {
+        println!(
+            "Updating {id} from {} to {}...",
+            describe(installed_version.as_deref()),
+            describe(available_version)
+        );
+    }
+    install_selected(
+        selected,
+        &available,
+        installation_args,
+        network,
+        context,
+        |context, result| {
+            context.emit(output::UpdateResult {
+                previous_version: installed_version.as_deref(),
+                installed: Some(result),
+            })
+        },
+    )
+    .await
+}
 
-    let params = humanode_distribution_installer::install::Params {
-        client,
+/// Install the selected package along with its dependencies, and emit
+/// the installation result with the given function under the JSON output.
+async fn install_selected(
+    selected: &Candidate,
+    available: &[Candidate],
+    installation_args: InstallationArgs,
+    network: Network,
+    context: &mut Context,
+    emit: impl FnOnce(&mut Context, output::InstallResult<'_>) -> Result<(), eyre::Error>,
+) -> Result<(), eyre::Error> {
+    let InstallationArgs {
         dir,
-        base_url: selected.manifest_url,
-        package: selected.value,
+        force,
+        limit_rate,
+    } = installation_args;
+
+    // The dependencies are picked from all the packages that suit the system,
+    // regardless of the `--where` expression; the requested package is
+    // installed last.
+    let steps = humanode_distribution_resolver::dependencies::plan(selected, available)?;
+
+    let resources = available_resources(&dir)?;
+    let packages = steps.iter().map(|step| &step.package.package.value);
+    match humanode_distribution_installer::preflight::check(packages, &resources) {
+        Ok(()) => {}
+        Err(error) if force => eprintln!("Warning: {error}, installing anyway"),
+        Err(error) => return Err(eyre::eyre!(error).wrap_err("use --force to install anyway")),
+    }
+
+    let step_dir = |sub_path: &Option<String>| {
+        humanode_distribution_installer::composite::part_dir(dir.as_ref(), sub_path.as_deref())
+    };
+    if context.output == Format::Text {
+        for step in &steps {
+            println!(
+                "Installing {:?} to {:?}...",
+                step.package.package.value.display_name,
+                step_dir(&step.sub_path)
+            );
+        }
+    }
+
+    let Network {
+        transport,
+        retry_policy,
+        max_bytes_per_second,
+    } = network;
+    let rate_limiter = limit_rate
+        .or(max_bytes_per_second)
+        .map(humanode_distribution_installer::rate::Limiter::new);
+
+    let parts = steps
+        .iter()
+        .map(|step| {
+            let Contextualized {
+                manifest_url,
+                mirror_urls,
+                value,
+                ..
+            } = step.package.package.clone();
+            humanode_distribution_installer::composite::Part {
+                sub_path: step.sub_path.clone(),
+                base_url: manifest_url,
+                mirror_urls,
+                package: value,
+            }
+        })
+        .collect();
+
+    let params = humanode_distribution_installer::composite::Params {
+        transport,
+        retry_policy,
+        dir: dir.clone().into(),
+        parts,
+        rate_limiter,
     };
 
-    humanode_distribution_installer::install::install(params).await?;
+    let installed = humanode_distribution_installer::composite::install(
+        params,
+        humanode_distribution::download_printer::Stderr::new(),
+    )
+    .await?;
+
+    if context.output == Format::Json {
+        let mut results = steps
+            .iter()
+            .zip(installed)
+            .map(|(step, installed)| output::InstallResult {
+                package: PackageEntry::new(&step.package.package, step.package.fit.support),
+                dir: step_dir(&step.sub_path).display().to_string(),
+                files: installed.files.into_iter().map(Into::into).collect(),
+                dependencies: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        // The requested package is the last step, after its dependencies.
+        if let Some(mut result) = results.pop() {
+            result.dependencies = results;
+            return emit(context, result);
+        }
+    }
 
     Ok(())
 }
 
+/// Parse the download rate, like `500K` or `5M`; the suffixes are binary.
+fn parse_rate(value: &str) -> Result<NonZeroU64, String> {
+    let (number, multiplier) = match value.as_bytes().last() {
+        Some(b'k' | b'K') => (&value[..value.len() - 1], 1 << 10),
+        Some(b'm' | b'M') => (&value[..value.len() - 1], 1 << 20),
+        Some(b'g' | b'G') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid rate {value:?}"))?;
+    number
+        .checked_mul(multiplier)
+        .and_then(NonZeroU64::new)
+        .ok_or_else(|| format!("the rate {value:?} is out of range"))
+}
+
+/// Detect the resources available for the installation into a given directory.
+fn available_resources(
+    dir: &str,
+) -> Result<humanode_distribution_installer::preflight::Available, eyre::Error> {
+    let detected = humanode_distribution_detection::detect()?;
+    let disk_space = match humanode_distribution_detection::free_space(dir.as_ref()) {
+        Ok(disk_space) => Some(disk_space),
+        Err(error) => {
+            eprintln!("Warning: unable to check the free disk space at {dir:?}: {error}");
+            None
+        }
+    };
+    Ok(humanode_distribution_installer::preflight::Available {
+        disk_space,
+        memory: detected.memory,
+        cpu_cores: detected.cpu_cores,
+    })
+}
+
 /// Sources command.
-async fn sources(args: Sources) -> Result<(), eyre::Error> {
+async fn sources(args: Sources, context: &mut Context) -> Result<(), eyre::Error> {
     let Sources { sources_args } = args;
-    let sources = prepare_sources(sources_args).await;
+    let sources = prepare_sources(sources_args).await?;
+    if context.output == Format::Json {
+        return context.emit(sources);
+    }
     println!("{}", &serde_yaml_bw::to_string(&sources)?);
     Ok(())
 }
@@ -0,0 +1,365 @@
+//! The machine-readable output.
+//!
+//! Every command emits a single JSON document to the stdout, both on
+//! success and on failure.
+
+use std::{ffi::OsStr, io::Write, time::SystemTime};
+
+use humanode_distribution_installer::{
+    install::{InstallationError, InstalledFile},
+    preflight::PreflightError,
+};
+use humanode_distribution_resolver::{
+    dependencies::DependencyError,
+    filter::Support,
+    resolve::{Contextualized, ResolutionError},
+};
+use humanode_distribution_schema::manifest::Package;
+use serde::Serialize;
+
+use crate::{picker::PickError, selector::SelectionError};
+
+/// The version of the documents schema; bumped on incompatible changes.
+pub const VERSION: u32 = 1;
+
+/// The output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Human-readable text.
+    Text,
+    /// JSON documents.
+    Json,
+}
+
+impl Format {
+    /// Find out the format requested by the raw command line arguments, for
+    /// reporting the errors of parsing them; the last `--output` wins, and
+    /// the text is used if there is none or its value is invalid.
+    pub fn requested<I>(args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        let mut format = Self::Text;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            let value = if arg == "--" {
+                break;
+            } else if arg == "--output" {
+                args.next()
+                    .and_then(|value| value.as_ref().to_str().map(str::to_owned))
+            } else {
+                arg.to_str()
+                    .and_then(|arg| arg.strip_prefix("--output="))
+                    .map(str::to_owned)
+            };
+            match value.as_deref() {
+                Some("json") => format = Self::Json,
+                Some("text") => format = Self::Text,
+                _ => {}
+            }
+        }
+        format
+    }
+}
+
+/// A document describing the command outcome.
+#[derive(Debug, Serialize)]
+pub struct Document<T> {
+    /// The version of the documents schema.
+    pub version: u32,
+    /// The command result, on success.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+    /// The error, on failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+    /// The issues encountered during the resolution.
+    pub issues: Vec<Issue>,
+}
+
+impl<T: Serialize> Document<T> {
+    /// Print the document to the stdout.
+    pub fn print(&self) -> Result<(), serde_json::Error> {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut stdout, self)?;
+        writeln!(stdout).map_err(serde_json::Error::io)
+    }
+}
+
+/// The command failure.
+#[derive(Debug, Serialize)]
+pub struct Error {
+    /// The failure class.
+    pub kind: ErrorKind,
+    /// The process exit code.
+    pub exit_code: u8,
+    /// The error message, along with its causes.
+    pub message: String,
+}
+
+impl From<&clap::Error> for Error {
+    fn from(error: &clap::Error) -> Self {
+        Self {
+            kind: ErrorKind::Usage,
+            exit_code: ErrorKind::Usage.exit_code(),
+            message: error.to_string().trim_end().to_owned(),
+        }
+    }
+}
+
+impl From<&eyre::Error> for Error {
+    fn from(error: &eyre::Error) -> Self {
+        let kind = ErrorKind::classify(error);
+        Self {
+            kind,
+            exit_code: kind.exit_code(),
+            message: format!("{error:#}"),
+        }
+    }
+}
+
+/// The failure class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Unclassified failure.
+    Other,
+    /// The command line is invalid.
+    Usage,
+    /// The packages or their dependencies could not be resolved.
+    Resolution,
+    /// No package could be selected.
+    Selection,
+    /// The system does not have the resources the package needs.
+    Preflight,
+    /// The installation failed.
+    Installation,
+}
+
+impl ErrorKind {
+    /// Find out the failure class by the errors in the chain.
+    pub fn classify(error: &eyre::Error) -> Self {
+        for cause in error.chain() {
+            if cause.is::<ResolutionError>() || cause.is::<DependencyError>() {
+                return Self::Resolution;
+            }
+            if cause.is::<SelectionError>() || cause.is::<PickError>() {
+                return Self::Selection;
+            }
+            if cause.is::<PreflightError>() {
+                return Self::Preflight;
+            }
+            if cause.is::<InstallationError>() {
+                return Self::Installation;
+            }
+        }
+        Self::Other
+    }
+
+    /// The process exit code for the failure class.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Other => 1,
+            Self::Usage => 2,
+            Self::Resolution => 3,
+            Self::Selection => 4,
+            Self::Preflight => 5,
+            Self::Installation => 6,
+        }
+    }
+}
+
+/// An issue encountered during the resolution.
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    /// The kind of the document that failed to load, `repo` or `manifest`.
+    pub document: String,
+    /// The URL of the document.
+    pub url: String,
+    /// The URLs of the documents that include this one, outermost first;
+    /// empty if the document was given directly.
+    pub include_chain: Vec<String>,
+    /// The error message.
+    pub message: String,
+}
+
+impl From<&ResolutionError> for Issue {
+    fn from(error: &ResolutionError) -> Self {
+        Self {
+            document: error.document.to_string(),
+            url: error.url.clone(),
+            include_chain: error.include_chain.clone(),
+            message: error.kind.to_string(),
+        }
+    }
+}
+
+/// A resolved package.
+#[derive(Debug, Serialize)]
+pub struct PackageEntry<'a> {
+    /// The manifest URL the package came from.
+    pub manifest_url: &'a str,
+    /// The mirror URLs declared by the manifest.
+    pub mirror_urls: &'a [String],
+    /// The time the manifest was fetched at, in RFC 3339 format, if it was
+    /// served from the cache.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_at: Option<String>,
+    /// Whether the package runs natively or via binary translation.
+    pub support: SupportKind,
+    /// The package itself, as in the manifest.
+    pub package: &'a Package,
+}
+
+impl<'a> PackageEntry<'a> {
+    /// Describe the package.
+    pub fn new(package: &'a Contextualized<Package>, support: Support) -> Self {
+        Self {
+            manifest_url: &package.manifest_url,
+            mirror_urls: &package.mirror_urls,
+            cached_at: package.cached_at.map(format_time),
+            support: support.into(),
+            package: &package.value,
+        }
+    }
+}
+
+/// The way the package runs on the system.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupportKind {
+    /// Natively.
+    Native,
+    /// Via binary translation.
+    Emulated,
+}
+
+impl From<Support> for SupportKind {
+    fn from(support: Support) -> Self {
+        match support {
+            Support::Native => Self::Native,
+            Support::Emulated => Self::Emulated,
+        }
+    }
+}
+
+/// The installation result.
+#[derive(Debug, Serialize)]
+pub struct InstallResult<'a> {
+    /// The installed package.
+    pub package: PackageEntry<'a>,
+    /// The directory the package was installed to.
+    pub dir: String,
+    /// The installed files.
+    pub files: Vec<FileEntry>,
+    /// The dependencies installed before the package, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<InstallResult<'a>>,
+}
+
+/// The update result.
+#[derive(Debug, Serialize)]
+pub struct UpdateResult<'a> {
+    /// The version installed before the update, if it had one.
+    pub previous_version: Option<&'a str>,
+    /// The installation of the newer version, if there is one.
+    pub installed: Option<InstallResult<'a>>,
+}
+
+/// An installed file.
+#[derive(Debug, Serialize)]
+pub struct FileEntry {
+    /// The path to the file.
+    pub path: String,
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+impl From<InstalledFile> for FileEntry {
+    fn from(file: InstalledFile) -> Self {
+        Self {
+            path: file.path.display().to_string(),
+            size: file.size,
+        }
+    }
+}
+
+/// Format the time in RFC 3339 format.
+fn format_time(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_errors() {
+        let error = eyre::Error::new(SelectionError::NoPackages).wrap_err("selecting");
+        let kind = ErrorKind::classify(&error);
+        assert_eq!(kind, ErrorKind::Selection);
+        assert_eq!(kind.exit_code(), 4);
+
+        let error = eyre::eyre!("something else");
+        assert_eq!(ErrorKind::classify(&error), ErrorKind::Other);
+
+        let document = Document::<()> {
+            version: VERSION,
+            result: None,
+            error: Some(Error::from(&error)),
+            issues: Vec::new(),
+        };
+        assert_eq!(
+            serde_json::to_value(&document).unwrap(),
+            serde_json::json!({
+                "version": 1,
+                "error": {
+                    "kind": "other",
+                    "exit_code": 1,
+                    "message": "something else",
+                },
+                "issues": [],
+            })
+        );
+    }
+
+    #[test]
+    fn finds_the_requested_format() {
+        let cases: [(&[&str], Format); 6] = [
+            (&["list"], Format::Text),
+            (&["list", "--output", "json"], Format::Json),
+            (&["--output=json", "list", "--bogus"], Format::Json),
+            (
+                &["list", "--output", "json", "--output", "text"],
+                Format::Text,
+            ),
+            (&["list", "--output", "yaml"], Format::Text),
+            (&["list", "--", "--output", "json"], Format::Text),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(Format::requested(args), expected, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_include_chain_order() {
+        let error = ResolutionError {
+            url: "https://example.com/manifest.yml".to_owned(),
+            document: humanode_distribution_resolver::resolve::Document::Manifest,
+            include_chain: vec![
+                "https://example.com/outer.yml".to_owned(),
+                "https://example.com/inner.yml".to_owned(),
+            ],
+            kind: humanode_distribution_resolver::resolve::ErrorKind::NoCache,
+        };
+        assert_eq!(
+            serde_json::to_value(Issue::from(&error)).unwrap()["include_chain"],
+            serde_json::json!([
+                "https://example.com/outer.yml",
+                "https://example.com/inner.yml",
+            ])
+        );
+    }
+}
@@ -1,41 +1,358 @@
 //! Package render.
 
+use humanode_distribution_resolver::{filter::Support, resolve::Contextualized};
 use humanode_distribution_schema::manifest::Package;
 
 /// Rendering params.
 #[derive(Debug, Clone, clap::ValueEnum)]
 pub enum Renderer {
-    /// Display name only.
+    /// Display name only, marked if the package runs via binary translation.
     DisplayName,
     /// YAML format.
     Yaml,
     /// JSON format.
     Json,
+    /// A table with a row per package, aligned across the packages.
+    Table,
 }
 
 impl Renderer {
-    /// Render the package into a writer according to the renderer config.
+    /// Render the package, running on the system the given way, into
+    /// a writer according to the renderer config.
     pub fn render(
         &self,
         mut writer: impl std::io::Write,
-        package: &Package,
+        package: &Contextualized<Package>,
+        support: Support,
     ) -> Result<(), eyre::Error> {
+        let value = &package.value;
         match self {
-            Self::DisplayName => writer
-                .write_all(package.display_name.as_bytes())
-                .map_err(Into::into),
+            Self::DisplayName => {
+                writer.write_all(value.display_name.as_bytes())?;
+                if support == Support::Emulated {
+                    writer.write_all(b" (emulated)")?;
+                }
+                Ok(())
+            }
             Self::Yaml => {
                 writer.write_all(b"---\n")?;
-                serde_yaml_bw::to_writer(writer, package).map_err(Into::into)
+                serde_yaml_bw::to_writer(writer, value).map_err(Into::into)
             }
-            Self::Json => serde_json::to_writer_pretty(writer, package).map_err(Into::into),
+            Self::Json => serde_json::to_writer_pretty(writer, value).map_err(Into::into),
+            Self::Table => render_table(writer, &[(package, support)]),
         }
     }
 
     /// Render the package into a string according to the renderer config.
-    pub fn render_to_string(&self, package: &Package) -> Result<String, eyre::Error> {
+    pub fn render_to_string(
+        &self,
+        package: &Contextualized<Package>,
+        support: Support,
+    ) -> Result<String, eyre::Error> {
         let mut buf = Vec::new();
-        self.render(&mut buf, package)?;
+        self.render(&mut buf, package, support)?;
         Ok(String::from_utf8(buf)?)
     }
 }
+
+/// The table columns: the header and the field to show.
+const COLUMNS: [(&str, Field); 6] = [
+    ("NAME", Field::DisplayName),
+    ("VERSION", Field::Version),
+    ("PLATFORM", Field::Platform),
+    ("ARCH", Field::Arch),
+    ("SUPPORT", Field::Support),
+    ("MANIFEST URL", Field::ManifestUrl),
+];
+
+/// Render the packages, along with the way they run on the system, as
+/// a table with a header, aligning the columns.
+///
+/// The table has no trailing newline, like the other renderers output.
+pub fn render_table(
+    mut writer: impl std::io::Write,
+    packages: &[(&Contextualized<Package>, Support)],
+) -> Result<(), eyre::Error> {
+    let widths: Vec<usize> = COLUMNS
+        .iter()
+        .map(|(header, field)| {
+            packages
+                .iter()
+                .map(|&(package, support)| field.value(package, support).chars().count())
+                .fold(header.len(), usize::max)
+        })
+        .collect();
+
+    let headers = COLUMNS.iter().map(|(header, _)| *header);
+    write_row(&mut writer, headers, &widths)?;
+    for &(package, support) in packages {
+        writer.write_all(b"\n")?;
+        let cells = COLUMNS
+            .iter()
+            .map(|(_, field)| field.value(package, support));
+        write_row(&mut writer, cells, &widths)?;
+    }
+    Ok(())
+}
+
+/// Write a single table row, padding the cells but the last one.
+fn write_row<'a>(
+    writer: &mut impl std::io::Write,
+    cells: impl Iterator<Item = &'a str>,
+    widths: &[usize],
+) -> Result<(), eyre::Error> {
+    let mut row = String::new();
+    for (index, (cell, width)) in cells.zip(widths).enumerate() {
+        if index + 1 == widths.len() {
+            row.push_str(cell);
+        } else {
+            row.push_str(&format!("{cell:<width$}  "));
+        }
+    }
+    writer.write_all(row.as_bytes())?;
+    Ok(())
+}
+
+/// A user-supplied template to render the packages with, like
+/// `{displayName}\t{manifestUrl}`.
+///
+/// The `\t`, `\n` and `\\` escapes are supported, and `{{` and `}}` stand
+/// for the literal braces.
+#[derive(Debug, Clone)]
+pub struct Template {
+    /// The parsed template parts.
+    segments: Vec<Segment>,
+}
+
+/// A part of the template.
+#[derive(Debug, Clone)]
+enum Segment {
+    /// Literal text.
+    Text(String),
+    /// A field substitution.
+    Field(Field),
+}
+
+/// A field the template can refer to.
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    /// The package identifier, empty if there is none.
+    Id,
+    /// The package display name.
+    DisplayName,
+    /// The package version, empty if there is none.
+    Version,
+    /// The package description.
+    Description,
+    /// The package platform.
+    Platform,
+    /// The package arch.
+    Arch,
+    /// The way the package runs on the system, `native` or `emulated`.
+    Support,
+    /// The URL of the manifest the package came from.
+    ManifestUrl,
+}
+
+impl Field {
+    /// All the fields along with their names.
+    const ALL: [(&'static str, Self); 8] = [
+        ("id", Self::Id),
+        ("displayName", Self::DisplayName),
+        ("version", Self::Version),
+        ("description", Self::Description),
+        ("platform", Self::Platform),
+        ("arch", Self::Arch),
+        ("support", Self::Support),
+        ("manifestUrl", Self::ManifestUrl),
+    ];
+
+    /// Obtain the field value.
+    fn value(self, package: &Contextualized<Package>, support: Support) -> &str {
+        match self {
+            Self::Id => package.value.id.as_ref().map_or("", |id| &id.0),
+            Self::DisplayName => &package.value.display_name,
+            Self::Version => package
+                .value
+                .version
+                .as_ref()
+                .map_or("", |version| &version.0),
+            Self::Description => &package.value.description,
+            Self::Platform => &package.value.platform.0,
+            Self::Arch => &package.value.arch.0,
+            Self::Support => match support {
+                Support::Native => "native",
+                Support::Emulated => "emulated",
+            },
+            Self::ManifestUrl => &package.manifest_url,
+        }
+    }
+}
+
+/// An error that can occur when parsing a template.
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    /// A field that does not exist is referenced.
+    #[error("unknown field {0:?}, expected one of id, displayName, version, description, platform, arch, support or manifestUrl")]
+    UnknownField(String),
+    /// A brace is not closed.
+    #[error("unclosed brace")]
+    UnclosedBrace,
+    /// A closing brace is not escaped.
+    #[error("unmatched closing brace, use }}}} for a literal one")]
+    UnmatchedBrace,
+}
+
+impl std::str::FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.peek() {
+                    Some('t') => {
+                        chars.next();
+                        text.push('\t');
+                    }
+                    Some('n') => {
+                        chars.next();
+                        text.push('\n');
+                    }
+                    Some('\\') => {
+                        chars.next();
+                        text.push('\\');
+                    }
+                    _ => text.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(TemplateError::UnmatchedBrace),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(TemplateError::UnclosedBrace),
+                        }
+                    }
+                    let field = Field::ALL
+                        .iter()
+                        .find(|(field_name, _)| *field_name == name.trim())
+                        .map(|(_, field)| *field)
+                        .ok_or(TemplateError::UnknownField(name))?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments })
+    }
+}
+
+impl Template {
+    /// Render the package, running on the system the given way, according
+    /// to the template.
+    pub fn render(&self, package: &Contextualized<Package>, support: Support) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.as_str(),
+                Segment::Field(field) => field.value(package, support),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::{manifest::Version, test_utils};
+
+    use super::*;
+
+    /// A package from the given manifest.
+    fn package(display_name: &str, manifest_url: &str) -> Contextualized<Package> {
+        Contextualized {
+            manifest_url: manifest_url.into(),
+            mirror_urls: Vec::new(),
+            cached_at: None,
+            value: test_utils::package(display_name),
+        }
+    }
+
+    #[test]
+    fn renders_templates() {
+        let template: Template = r"{displayName}\t{ manifestUrl } {{arch}}".parse().unwrap();
+        assert_eq!(
+            template.render(
+                &package("Peer", "https://example.com/manifest.json"),
+                Support::Native
+            ),
+            "Peer\thttps://example.com/manifest.json {arch}"
+        );
+
+        let mut versioned = package("Peer", "https://example.com/manifest.json");
+        versioned.value.version = Some(Version("0.1.0".into()));
+        let template: Template = "{displayName} {version} {support}".parse().unwrap();
+        assert_eq!(
+            template.render(&versioned, Support::Emulated),
+            "Peer 0.1.0 emulated"
+        );
+
+        assert!(matches!(
+            "{size}".parse::<Template>(),
+            Err(TemplateError::UnknownField(name)) if name == "size"
+        ));
+        assert!(matches!(
+            "{displayName".parse::<Template>(),
+            Err(TemplateError::UnclosedBrace)
+        ));
+    }
+
+    #[test]
+    fn aligns_tables() {
+        let mut first = package("Peer", "https://example.com/a.json");
+        first.value.version = Some(Version("0.1.0".into()));
+        let second = package("Humanode Peer", "https://example.com/b.json");
+        let mut buf = Vec::new();
+        render_table(
+            &mut buf,
+            &[(&first, Support::Native), (&second, Support::Emulated)],
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "\
+NAME           VERSION  PLATFORM  ARCH    SUPPORT   MANIFEST URL
+Peer           0.1.0    Linux     x86_64  native    https://example.com/a.json
+Humanode Peer           Linux     x86_64  emulated  https://example.com/b.json"
+        );
+    }
+
+    #[test]
+    fn marks_emulated_display_names() {
+        let package = package("Peer", "https://example.com/manifest.json");
+        let render = |support| {
+            Renderer::DisplayName
+                .render_to_string(&package, support)
+                .unwrap()
+        };
+        assert_eq!(render(Support::Native), "Peer");
+        assert_eq!(render(Support::Emulated), "Peer (emulated)");
+    }
+}
@@ -0,0 +1,106 @@
+//! Interactive package picker.
+
+use std::io::{BufRead, Write};
+
+use humanode_distribution_schema::manifest::Package;
+
+/// An error that can occur while picking a package.
+#[derive(Debug, thiserror::Error)]
+pub enum PickError {
+    /// Reading the answer or writing the prompt failed.
+    #[error("unable to prompt for the package: {0}")]
+    Io(#[from] std::io::Error),
+    /// The input ended without a package picked.
+    #[error("no package was picked")]
+    Cancelled,
+}
+
+/// Ask the user to pick one of the packages, listing their display names,
+/// descriptions and sources.
+///
+/// The prompt is repeated until a valid number is entered.
+pub fn pick<T: AsRef<Package>>(
+    mut packages: Vec<T>,
+    source: impl Fn(&T) -> &str,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> Result<T, PickError> {
+    writeln!(output, "More than one package is available:")?;
+    for (index, package) in packages.iter().enumerate() {
+        let Package {
+            display_name,
+            description,
+            ..
+        } = package.as_ref();
+        writeln!(output, "  {}) {display_name}", index + 1)?;
+        writeln!(output, "     {description}")?;
+        writeln!(output, "     from {}", source(package))?;
+    }
+
+    loop {
+        write!(output, "Pick a package [1-{}]: ", packages.len())?;
+        output.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            writeln!(output)?;
+            return Err(PickError::Cancelled);
+        }
+
+        match answer.trim().parse::<usize>() {
+            Ok(number) if (1..=packages.len()).contains(&number) => {
+                return Ok(packages.swap_remove(number - 1));
+            }
+            _ => writeln!(output, "Enter a number from 1 to {}", packages.len())?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::test_utils::package;
+
+    use super::*;
+
+    /// A package along with its source.
+    #[derive(Debug)]
+    struct Sourced(Package, &'static str);
+
+    impl AsRef<Package> for Sourced {
+        fn as_ref(&self) -> &Package {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn picks_by_number() {
+        let packages = vec![
+            Sourced(package("First"), "https://example.com/a.json"),
+            Sourced(package("Second"), "https://example.com/b.json"),
+        ];
+        let mut output = Vec::new();
+        let picked = pick(
+            packages,
+            |package| package.1,
+            &b"3\nsecond\n2\n"[..],
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(picked.0.display_name, "Second");
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output
+            .contains("  2) Second\n     Test package.\n     from https://example.com/b.json\n"));
+        assert_eq!(output.matches("Enter a number from 1 to 2").count(), 2);
+    }
+
+    #[test]
+    fn cancels_on_end_of_input() {
+        let packages = vec![
+            Sourced(package("First"), "a"),
+            Sourced(package("Second"), "b"),
+        ];
+        let error = pick(packages, |package| package.1, &b""[..], std::io::sink()).unwrap_err();
+        assert!(matches!(error, PickError::Cancelled));
+    }
+}
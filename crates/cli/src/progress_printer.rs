@@ -0,0 +1,142 @@
+//! Progress printers report the resolution progress by "printing" it.
+
+use std::{
+    io::{IsTerminal, Write},
+    task::Poll,
+    time::SystemTime,
+};
+
+use futures::Sink;
+use humanode_distribution_resolver::progress::Event;
+
+/// A sink that prints the progress to the stderr.
+///
+/// On a terminal the progress is shown as a status line that is updated in
+/// place, and as log lines otherwise.
+pub struct Stderr {
+    /// Whether to render the status line rather than the log lines.
+    live: bool,
+    /// The number of the packages found so far.
+    packages: usize,
+}
+
+impl Stderr {
+    /// Create a new printer, detecting whether the stderr is a terminal.
+    pub fn new() -> Self {
+        Self {
+            live: std::io::stderr().is_terminal(),
+            packages: 0,
+        }
+    }
+
+    /// Replace the status line with the given text.
+    fn status(&self, text: &str) {
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{text}");
+        let _ = stderr.flush();
+    }
+
+    /// Print the event as a status line.
+    fn print_live(&mut self, event: Event) {
+        match event {
+            Event::FetchStarted { url, document } => self.status(&format!(
+                "Resolving, {} packages found so far: fetching {document} {url}",
+                self.packages
+            )),
+            Event::FetchFinished { .. } | Event::FetchFailed { .. } => self.status(""),
+            Event::ManifestResolved {
+                url,
+                found,
+                filtered_out,
+                cached_at,
+            } => {
+                self.packages += found - filtered_out;
+                if let Some(cached_at) = cached_at {
+                    self.status("");
+                    warn_stale(&url, cached_at);
+                }
+            }
+        }
+    }
+
+    /// Print the event as a log line.
+    fn print_log(&mut self, event: Event) {
+        match event {
+            Event::FetchStarted { url, document } => eprintln!("Fetching {document} {url}"),
+            Event::FetchFinished {
+                url,
+                document,
+                bytes,
+                duration,
+            } => eprintln!("Fetched {document} {url}: {bytes} bytes in {duration:.2?}"),
+            Event::FetchFailed {
+                url,
+                document,
+                duration,
+            } => eprintln!("Failed to fetch {document} {url} after {duration:.2?}"),
+            Event::ManifestResolved {
+                url,
+                found,
+                filtered_out,
+                cached_at,
+            } => {
+                eprintln!(
+                    "Resolved manifest {url}: {found} packages found, {filtered_out} filtered out"
+                );
+                if let Some(cached_at) = cached_at {
+                    warn_stale(&url, cached_at);
+                }
+            }
+        }
+    }
+}
+
+/// Warn about the manifest that was served from the cache rather than
+/// fetched just now.
+fn warn_stale(url: &str, cached_at: SystemTime) {
+    eprintln!(
+        "Warning: using a stale cached copy of {url} fetched at {}",
+        humantime::format_rfc3339_seconds(cached_at)
+    );
+}
+
+impl Default for Stderr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sink<Event> for Stderr {
+    type Error = ();
+
+    fn poll_ready(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: std::pin::Pin<&mut Self>, item: Event) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        if this.live {
+            this.print_live(item);
+        } else {
+            this.print_log(item);
+        }
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -1,11 +1,25 @@
 //! Package selector.
 
+use humanode_distribution_resolver::resolve::Contextualized;
 use humanode_distribution_schema::manifest::Package;
 
+/// The maximum number of the suggestions to offer when nothing is found.
+const MAX_SUGGESTIONS: usize = 3;
+
 /// Package selector that's optimized for the CLI experience.
+#[derive(Debug, Default)]
 pub struct Selector {
+    /// The package identifier, optional; matched exactly.
+    pub package_id: Option<String>,
     /// The package display name, optional.
+    ///
+    /// The exact matches are preferred, then the case-insensitive ones, and
+    /// then the names that contain the given one regardless of the case.
     pub package_display_name: Option<String>,
+    /// The URL of the manifest the package must come from, optional.
+    pub manifest_url: Option<String>,
+    /// The 1-based position of the package in the list, optional.
+    pub index: Option<usize>,
 }
 
 /// An error that can occur during the selection.
@@ -17,28 +31,327 @@ pub enum SelectionError {
     /// When a more specific selector is required to narrow the choice.
     #[error("more than one package is available, use a selector to specify which one you want")]
     NotSpecificEnough,
+    /// When the name partially matches more than one package.
+    #[error("the name matches more than one package: {}", .matches.join(", "))]
+    Ambiguous {
+        /// The display names of the matching packages.
+        matches: Vec<String>,
+    },
     /// When the selector filtered out all the possible packages without landing on one.
-    #[error("unable to find the requested package")]
-    NotFound,
+    #[error("unable to find the requested package{}", did_you_mean(.suggestions))]
+    NotFound {
+        /// The similar display names or identifiers of the packages.
+        suggestions: Vec<String>,
+    },
+    /// When the index is past the list.
+    #[error("there is no package number {index}, only {available} are available")]
+    IndexOutOfRange {
+        /// The requested index.
+        index: usize,
+        /// The number of available packages.
+        available: usize,
+    },
+}
+
+/// Format the suggestions, if any.
+fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [suggestion] => format!(", did you mean {suggestion:?}?"),
+        [init @ .., last] => {
+            let init: Vec<_> = init.iter().map(|name| format!("{name:?}")).collect();
+            format!(", did you mean {} or {last:?}?", init.join(", "))
+        }
+    }
 }
 
 impl Selector {
     /// Select a package from the list.
-    pub fn select<T: AsRef<Package>>(&self, mut packages: Vec<T>) -> Result<T, SelectionError> {
-        let last = packages.pop().ok_or(SelectionError::NoPackages)?;
+    pub fn select<T: AsRef<Contextualized<Package>>>(
+        &self,
+        mut packages: Vec<T>,
+    ) -> Result<T, SelectionError> {
+        if packages.is_empty() {
+            return Err(SelectionError::NoPackages);
+        }
+
+        if let Some(ref manifest_url) = self.manifest_url {
+            packages.retain(|package| &package.as_ref().manifest_url == manifest_url);
+            if packages.is_empty() {
+                return Err(SelectionError::NotFound {
+                    suggestions: Vec::new(),
+                });
+            }
+        }
+
+        if let Some(ref package_id) = self.package_id {
+            let ids: Vec<String> = packages
+                .iter()
+                .filter_map(|package| package.as_ref().value.id.as_ref())
+                .map(|id| id.0.clone())
+                .collect();
+            packages.retain(|package| {
+                package
+                    .as_ref()
+                    .value
+                    .id
+                    .as_ref()
+                    .is_some_and(|id| &id.0 == package_id)
+            });
+            if packages.is_empty() {
+                return Err(SelectionError::NotFound {
+                    suggestions: suggest(&package_id.to_lowercase(), ids.into_iter()),
+                });
+            }
+        }
+
+        if let Some(index) = self.index {
+            if index == 0 || index > packages.len() {
+                return Err(SelectionError::IndexOutOfRange {
+                    index,
+                    available: packages.len(),
+                });
+            }
+            return Ok(packages.swap_remove(index - 1));
+        }
 
         let package_display_name = match self.package_display_name {
             Some(ref package_display_name) => package_display_name,
-            None if !packages.is_empty() => return Err(SelectionError::NotSpecificEnough),
-            None => return Ok(last),
+            // The builds sharing the identifier are ranked already, so take
+            // the first one.
+            None if self.package_id.is_some() => return Ok(packages.swap_remove(0)),
+            None if packages.len() > 1 => return Err(SelectionError::NotSpecificEnough),
+            None => return Ok(packages.swap_remove(0)),
+        };
+
+        let name = |package: &T| package.as_ref().value.display_name.clone();
+        let lowercase = package_display_name.to_lowercase();
+        let matchers: [&dyn Fn(&str) -> bool; 3] = [
+            &|candidate| candidate == package_display_name,
+            &|candidate| candidate.to_lowercase() == lowercase,
+            &|candidate| candidate.to_lowercase().contains(&lowercase),
+        ];
+        for matcher in matchers {
+            let Some(position) = packages.iter().position(|package| matcher(&name(package))) else {
+                continue;
+            };
+
+            // The same name can be shared by several builds of the package,
+            // and the first one is preferred then; different names are
+            // ambiguous though.
+            let mut matches: Vec<String> = Vec::new();
+            for candidate in packages.iter().map(name) {
+                if matcher(&candidate) && !matches.contains(&candidate) {
+                    matches.push(candidate);
+                }
+            }
+            if matches.len() > 1 {
+                return Err(SelectionError::Ambiguous { matches });
+            }
+            return Ok(packages.swap_remove(position));
+        }
+
+        Err(SelectionError::NotFound {
+            suggestions: suggest(&lowercase, packages.iter().map(name)),
+        })
+    }
+}
+
+/// Find the names similar to the given one, the closest first.
+fn suggest(lowercase: &str, names: impl Iterator<Item = String>) -> Vec<String> {
+    let max_distance = (lowercase.chars().count() / 3).max(1);
+    let mut suggestions: Vec<(usize, String)> = names
+        .map(|name| (distance(lowercase, &name.to_lowercase()), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    suggestions.sort();
+    suggestions.dedup_by(|a, b| a.1 == b.1);
+    suggestions
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// The Levenshtein distance between the strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::{manifest::PackageId, test_utils};
+
+    use super::*;
+
+    /// A package from the given manifest.
+    struct Item(Contextualized<Package>);
+
+    impl AsRef<Contextualized<Package>> for Item {
+        fn as_ref(&self) -> &Contextualized<Package> {
+            &self.0
+        }
+    }
+
+    impl std::fmt::Debug for Item {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(&self.0.value.display_name)
+        }
+    }
+
+    /// The test packages.
+    fn packages() -> Vec<Item> {
+        [
+            ("Humanode Peer", "https://a.example.com/manifest.json"),
+            ("Humanode Peer", "https://b.example.com/manifest.json"),
+            ("Internal Test", "https://a.example.com/manifest.json"),
+            ("Internal Bench", "https://b.example.com/manifest.json"),
+        ]
+        .into_iter()
+        .map(|(display_name, manifest_url)| {
+            let id = display_name.to_lowercase().replace(' ', "-");
+            Item(Contextualized {
+                manifest_url: manifest_url.into(),
+                mirror_urls: Vec::new(),
+                cached_at: None,
+                value: Package {
+                    id: Some(PackageId(id)),
+                    ..test_utils::package(display_name)
+                },
+            })
+        })
+        .collect()
+    }
+
+    /// Select by name.
+    fn by_name(name: &str) -> Result<Item, SelectionError> {
+        let selector = Selector {
+            package_display_name: Some(name.into()),
+            ..Default::default()
+        };
+        selector.select(packages())
+    }
+
+    #[test]
+    fn selects_by_exact_name() {
+        let selected = by_name("Humanode Peer").unwrap();
+        assert_eq!(
+            selected.0.manifest_url,
+            "https://a.example.com/manifest.json"
+        );
+    }
+
+    #[test]
+    fn selects_by_case_insensitive_name() {
+        let selected = by_name("internal test").unwrap();
+        assert_eq!(selected.0.value.display_name, "Internal Test");
+    }
+
+    #[test]
+    fn selects_by_partial_name() {
+        let selected = by_name("bench").unwrap();
+        assert_eq!(selected.0.value.display_name, "Internal Bench");
+
+        let error = by_name("internal").unwrap_err();
+        assert!(matches!(
+            error,
+            SelectionError::Ambiguous { ref matches }
+                if matches == &["Internal Test", "Internal Bench"]
+        ));
+    }
+
+    #[test]
+    fn suggests_similar_names() {
+        let error = by_name("Humanode Per").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unable to find the requested package, did you mean \"Humanode Peer\"?"
+        );
+
+        let error = by_name("Internal Best").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unable to find the requested package, did you mean \"Internal Test\" or \"Internal Bench\"?"
+        );
+
+        let error = by_name("something else").unwrap_err();
+        assert_eq!(error.to_string(), "unable to find the requested package");
+    }
+
+    #[test]
+    fn selects_by_id() {
+        let selector = Selector {
+            package_id: Some("humanode-peer".into()),
+            ..Default::default()
         };
+        let selected = selector.select(packages()).unwrap();
+        assert_eq!(
+            selected.0.manifest_url,
+            "https://a.example.com/manifest.json"
+        );
 
-        let selected = packages
-            .into_iter()
-            .chain(std::iter::once(last))
-            .find(|package| &package.as_ref().display_name == package_display_name)
-            .ok_or(SelectionError::NotFound)?;
+        let selector = Selector {
+            package_id: Some("internal-tset".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            selector.select(packages()).unwrap_err().to_string(),
+            "unable to find the requested package, did you mean \"internal-test\"?"
+        );
+    }
+
+    #[test]
+    fn selects_by_manifest_url() {
+        let selector = Selector {
+            manifest_url: Some("https://b.example.com/manifest.json".into()),
+            package_display_name: Some("Humanode Peer".into()),
+            ..Default::default()
+        };
+        let selected = selector.select(packages()).unwrap();
+        assert_eq!(
+            selected.0.manifest_url,
+            "https://b.example.com/manifest.json"
+        );
 
-        Ok(selected)
+        let selector = Selector {
+            manifest_url: Some("https://b.example.com/manifest.json".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            selector.select(packages()),
+            Err(SelectionError::NotSpecificEnough)
+        ));
+    }
+
+    #[test]
+    fn selects_by_index() {
+        let selector = Selector {
+            index: Some(3),
+            ..Default::default()
+        };
+        let selected = selector.select(packages()).unwrap();
+        assert_eq!(selected.0.value.display_name, "Internal Test");
+
+        let selector = Selector {
+            index: Some(5),
+            ..Default::default()
+        };
+        assert!(matches!(
+            selector.select(packages()),
+            Err(SelectionError::IndexOutOfRange {
+                index: 5,
+                available: 4
+            })
+        ));
     }
 }
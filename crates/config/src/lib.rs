@@ -20,6 +20,13 @@ pub mod dirs {
     pub const MANIFEST_URLS: &str = "manifestUrls.d";
 }
 
+pub mod files {
+    //! Files.
+
+    /// The file with the settings.
+    pub const SETTINGS: &str = "settings.yml";
+}
+
 /// The configured sources.
 ///
 /// This is not intended to be directly persisted, but rather [`load`]ed from
@@ -43,3 +50,24 @@ impl Sources {
         self.repo_urls.extend(repo_urls);
     }
 }
+
+/// The configured settings.
+///
+/// This is not intended to be directly persisted, but rather [`load`]ed from
+/// the various config files.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Settings {
+    /// The retry settings for the network requests.
+    pub retry: schemas::settings::Retry,
+    /// The download settings.
+    pub download: schemas::settings::Download,
+}
+
+impl Settings {
+    /// Override the settings with the values set in the other instance.
+    pub fn extend(&mut self, other: Self) {
+        let Self { retry, download } = other;
+        self.retry.extend(retry);
+        self.download.extend(download);
+    }
+}
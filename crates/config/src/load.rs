@@ -4,7 +4,7 @@ use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
-use crate::Sources;
+use crate::{Settings, Sources};
 
 /// The result of loading the sources.
 #[derive(Debug)]
@@ -93,6 +93,24 @@ pub async fn sources(path: impl AsRef<Path>) -> SourcesLoadingResult {
     SourcesLoadingResult { sources, errors }
 }
 
+/// Load the settings from the given path.
+///
+/// The path is the directory that contains `settings.yml`; the default
+/// settings are returned if the file does not exist.
+pub async fn settings(path: impl AsRef<Path>) -> Result<Settings, LoadingError> {
+    let path = path.as_ref().join(crate::files::SETTINGS);
+    let data = match tokio::fs::read(&path).await {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(err) => return Err(LoadingError::FileReading(err, path)),
+    };
+
+    let crate::schemas::settings::Format { retry, download } =
+        serde_yaml_bw::from_slice(&data).map_err(|err| LoadingError::Parsing(err, path))?;
+
+    Ok(Settings { retry, download })
+}
+
 /// Load the Repo URLs.
 async fn repo_urls(path: impl AsRef<Path>) -> (Vec<String>, Vec<LoadingError>) {
     load(path, |file: crate::schemas::repos::Format| {
@@ -5,6 +5,9 @@ use std::path::PathBuf;
 /// The subdirectories to look for the configs at.
 const SUBDIRS: &[&str] = &["humanode-launcher"];
 
+/// The subdirectory to keep the cached data at.
+const CACHE_SUBDIR: &str = "humanode-launcher";
+
 /// The various paths to attempt loading the configs from.
 pub fn configs() -> impl IntoIterator<Item = PathBuf> {
     let Some(dir) = dirs::config_dir() else {
@@ -16,3 +19,8 @@ pub fn configs() -> impl IntoIterator<Item = PathBuf> {
         .map(move |&subdirs| dir.join(subdirs))
         .collect::<Vec<_>>()
 }
+
+/// The path to keep the cached data at, if the platform has one.
+pub fn cache() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(CACHE_SUBDIR))
+}
@@ -2,7 +2,8 @@
 //!
 //! These should be no need to use these schemas outside of this crate, since
 //! the loading process resolves the files into
-//! the effective [`crate::Sources`].
+//! the effective [`crate::Sources`] and [`crate::Settings`].
 
 pub mod manifest_urls;
 pub mod repos;
+pub mod settings;
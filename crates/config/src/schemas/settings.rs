@@ -0,0 +1,85 @@
+//! The `settings.yml` file content.
+
+use std::num::NonZeroU64;
+
+use serde::{Deserialize, Serialize};
+
+/// The structure of the `settings.yml` file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Format {
+    /// The retry settings for the network requests.
+    #[serde(default)]
+    pub retry: Retry,
+    /// The download settings.
+    #[serde(default)]
+    pub download: Download,
+}
+
+/// The retry settings for the network requests.
+///
+/// The unset values are left to the defaults.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Retry {
+    /// The maximum number of attempts per request, including the first one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// The delay before the first retry, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_backoff_ms: Option<u64>,
+    /// The upper bound for the delay between the attempts, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_backoff_ms: Option<u64>,
+}
+
+impl Retry {
+    /// Override the values with the ones that are set in the other instance.
+    pub fn extend(&mut self, other: Self) {
+        let Self {
+            max_attempts,
+            initial_backoff_ms,
+            max_backoff_ms,
+        } = other;
+        self.max_attempts = max_attempts.or(self.max_attempts);
+        self.initial_backoff_ms = initial_backoff_ms.or(self.initial_backoff_ms);
+        self.max_backoff_ms = max_backoff_ms.or(self.max_backoff_ms);
+    }
+}
+
+/// The download settings.
+///
+/// The unset values are left to the defaults.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Download {
+    /// The maximum download rate, in bytes per second, shared by all
+    /// the downloads; zero is rejected, like with `--limit-rate`, and
+    /// the downloads are unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes_per_second: Option<NonZeroU64>,
+}
+
+impl Download {
+    /// Override the values with the ones that are set in the other instance.
+    pub fn extend(&mut self, other: Self) {
+        let Self {
+            max_bytes_per_second,
+        } = other;
+        self.max_bytes_per_second = max_bytes_per_second.or(self.max_bytes_per_second);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_zero_rate() {
+        let format: Format =
+            serde_yaml_bw::from_str("download:\n  maxBytesPerSecond: 1024\n").unwrap();
+        assert_eq!(format.download.max_bytes_per_second, NonZeroU64::new(1024));
+
+        assert!(serde_yaml_bw::from_str::<Format>("download:\n  maxBytesPerSecond: 0\n").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! The CPU feature detection.
+
+use std::collections::BTreeSet;
+
+/// Detect the features of the CPU the process runs on, along with
+/// the microarchitecture levels they satisfy.
+pub fn features() -> Vec<String> {
+    let mut features: BTreeSet<String> = detect_features().into_iter().map(str::to_owned).collect();
+    let levels = humanode_distribution_platform::cpu::levels(&features);
+    features.extend(levels.into_iter().map(str::to_owned));
+    features.into_iter().collect()
+}
+
+/// Collect the names of the detected features.
+macro_rules! detect {
+    ($macro:ident, $($feature:tt),* $(,)?) => {{
+        let mut features = Vec::new();
+        $(
+            if std::arch::$macro!($feature) {
+                features.push($feature);
+            }
+        )*
+        features
+    }};
+}
+
+/// Detect the x86-64 CPU features.
+#[cfg(target_arch = "x86_64")]
+fn detect_features() -> Vec<&'static str> {
+    detect!(
+        is_x86_feature_detected,
+        "cmpxchg16b",
+        "popcnt",
+        "sse3",
+        "sse4.1",
+        "sse4.2",
+        "ssse3",
+        "avx",
+        "avx2",
+        "bmi1",
+        "bmi2",
+        "f16c",
+        "fma",
+        "lzcnt",
+        "movbe",
+        "xsave",
+        "avx512f",
+        "avx512bw",
+        "avx512cd",
+        "avx512dq",
+        "avx512vl",
+        "aes",
+        "pclmulqdq",
+        "sha",
+    )
+}
+
+/// Detect the AArch64 CPU features.
+#[cfg(target_arch = "aarch64")]
+fn detect_features() -> Vec<&'static str> {
+    detect!(
+        is_aarch64_feature_detected,
+        "neon",
+        "aes",
+        "pmull",
+        "sha2",
+        "sha3",
+        "crc",
+        "lse",
+        "rdm",
+        "dotprod",
+        "fp16",
+        "rcpc",
+        "sve",
+        "sve2",
+    )
+}
+
+/// Detect the CPU features; none are of interest on this architecture.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn detect_features() -> Vec<&'static str> {
+    Vec::new()
+}
@@ -2,7 +2,7 @@
 
 use std::ffi::{c_char, CStr};
 
-use crate::Info;
+use crate::{Info, Libc};
 
 /// Obtain the system information.
 #[allow(unsafe_code)]
@@ -11,10 +11,50 @@ pub fn detect() -> std::io::Result<Info> {
     if unsafe { libc::uname(&mut value) } != 0 {
         return Err(std::io::Error::last_os_error());
     }
-    Ok(Info {
+    let distribution = crate::os_release::read();
+    let mut info = Info {
         platform: to_string(&value.sysname[..]),
         arch: to_string(&value.machine[..]),
-    })
+        translated_arches: Vec::new(),
+        cpu_features: crate::cpu::features(),
+        kernel_release: Some(to_string(&value.release[..])),
+        os_version: product_version().or_else(|| {
+            distribution
+                .as_ref()
+                .and_then(|distribution| distribution.version_id.clone())
+        }),
+        distribution,
+        libc: detect_libc(),
+        memory: memory(),
+        cpu_cores: std::thread::available_parallelism().ok().map(Into::into),
+    };
+    detect_translation(&mut info);
+    Ok(info)
+}
+
+/// Obtain the free space available to the unprivileged users on
+/// the file system the path is at.
+#[allow(unsafe_code)]
+pub fn free_space(path: &std::path::Path) -> std::io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // The field types vary across the systems.
+    #[allow(clippy::useless_conversion)]
+    Ok(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+}
+
+/// Obtain the total physical memory.
+#[allow(unsafe_code)]
+fn memory() -> Option<u64> {
+    let pages = unsafe { libc::sysconf(libc::_SC_PHYS_PAGES) };
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(u64::try_from(pages).ok()? * u64::try_from(page_size).ok()?)
 }
 
 /// Assume a valid C string and copy it into a new Rust string with a lossy
@@ -24,3 +64,130 @@ fn to_string(buf: &[c_char]) -> String {
     let c_str = unsafe { CStr::from_ptr(buf.as_ptr()) };
     c_str.to_string_lossy().into_owned()
 }
+
+/// Detect the Rosetta 2 translation on Apple Silicon.
+#[cfg(target_os = "macos")]
+fn detect_translation(info: &mut Info) {
+    /// The Rosetta 2 runtime, present when Rosetta 2 is installed.
+    const ROSETTA_RUNTIME: &str = "/Library/Apple/usr/libexec/oah/libRosettaRuntime";
+
+    let translated = is_translated();
+    if translated {
+        // The translated process sees the emulated architecture in `uname`,
+        // and the emulated CPU features.
+        info.arch = "arm64".into();
+        info.cpu_features.clear();
+    }
+    if info.arch == "arm64" && (translated || std::path::Path::new(ROSETTA_RUNTIME).exists()) {
+        info.translated_arches.push("x86_64".into());
+    }
+}
+
+/// Detect the binary translation; there is none we know of on this system.
+#[cfg(not(target_os = "macos"))]
+fn detect_translation(_info: &mut Info) {}
+
+/// Obtain the macOS product version, like `14.2.1`.
+#[cfg(target_os = "macos")]
+#[allow(unsafe_code)]
+fn product_version() -> Option<String> {
+    let name = c"kern.osproductversion";
+    let mut size = 0;
+    let ret = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            std::ptr::null_mut(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 || size == 0 {
+        return None;
+    }
+
+    let mut buf = vec![0u8; size];
+    let ret = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            buf.as_mut_ptr().cast(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 {
+        return None;
+    }
+
+    let value = CStr::from_bytes_until_nul(&buf).ok()?;
+    Some(value.to_string_lossy().into_owned())
+}
+
+/// Obtain the product version; there is no such thing on this system.
+#[cfg(not(target_os = "macos"))]
+fn product_version() -> Option<String> {
+    None
+}
+
+/// Detect the C library the process is linked against.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[allow(unsafe_code)]
+fn detect_libc() -> Option<Libc> {
+    let version = unsafe { CStr::from_ptr(libc::gnu_get_libc_version()) };
+    Some(Libc {
+        kind: crate::LibcKind::Glibc,
+        version: Some(version.to_string_lossy().into_owned()),
+    })
+}
+
+/// Detect the C library the system uses by looking for its dynamic loader.
+///
+/// The version is unknown, as the process is not linked against it.
+#[cfg(all(target_os = "linux", not(target_env = "gnu")))]
+fn detect_libc() -> Option<Libc> {
+    let entries = |dir: &str| -> Vec<String> {
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect()
+    };
+    let loaders: Vec<String> = ["/lib", "/lib64"].into_iter().flat_map(entries).collect();
+
+    let kind = if loaders.iter().any(|name| name.starts_with("ld-musl-")) {
+        crate::LibcKind::Musl
+    } else if loaders.iter().any(|name| name.starts_with("ld-linux")) {
+        crate::LibcKind::Glibc
+    } else {
+        return None;
+    };
+    Some(Libc {
+        kind,
+        version: None,
+    })
+}
+
+/// Detect the C library; only the Linux ones are of interest.
+#[cfg(not(target_os = "linux"))]
+fn detect_libc() -> Option<Libc> {
+    None
+}
+
+/// Check whether the current process runs under Rosetta 2.
+#[cfg(target_os = "macos")]
+#[allow(unsafe_code)]
+fn is_translated() -> bool {
+    let mut value: libc::c_int = 0;
+    let mut size = std::mem::size_of::<libc::c_int>();
+    let ret = unsafe {
+        libc::sysctlbyname(
+            c"sysctl.proc_translated".as_ptr(),
+            (&mut value as *mut libc::c_int).cast(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    ret == 0 && value == 1
+}
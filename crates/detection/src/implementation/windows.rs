@@ -11,5 +11,19 @@ pub fn detect() -> std::io::Result<Info> {
     Ok(Info {
         platform: "Windows".into(),
         arch: arch.into(),
+        translated_arches: Vec::new(),
+        cpu_features: crate::cpu::features(),
+        kernel_release: None,
+        os_version: None,
+        distribution: None,
+        libc: None,
+        memory: None,
+        cpu_cores: std::thread::available_parallelism().ok().map(Into::into),
     })
 }
+
+/// Obtain the free space on the file system the path is at; not supported
+/// on this system yet.
+pub fn free_space(_path: &std::path::Path) -> std::io::Result<u64> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
@@ -8,16 +8,86 @@ mod implementation;
 #[path = "implementation/windows.rs"]
 mod implementation;
 
+mod cpu;
+#[cfg(not(windows))]
+mod os_release;
+
+pub use humanode_distribution_schema::manifest::LibcKind;
+
 /// Info about the system.
 #[derive(Debug)]
 pub struct Info {
-    /// The name of the system architecture.
+    /// The canonical name of the system architecture.
     pub arch: String,
-    /// The name of the platform.
+    /// The canonical name of the platform.
     pub platform: String,
+    /// The canonical names of the architectures the system can run via
+    /// binary translation, such as Rosetta 2, in the order of preference.
+    pub translated_arches: Vec<String>,
+    /// The features of the CPU, along with the microarchitecture levels
+    /// they satisfy, like `x86-64-v3`; see
+    /// [`humanode_distribution_platform::cpu`].
+    pub cpu_features: Vec<String>,
+    /// The kernel release, as reported by `uname -r`.
+    pub kernel_release: Option<String>,
+    /// The OS version: the product version on macOS, and the version of
+    /// the distribution on Linux.
+    pub os_version: Option<String>,
+    /// The Linux distribution.
+    pub distribution: Option<Distribution>,
+    /// The C library the system uses.
+    pub libc: Option<Libc>,
+    /// The total physical memory, in bytes.
+    pub memory: Option<u64>,
+    /// The number of the CPU cores available to the process.
+    pub cpu_cores: Option<usize>,
+}
+
+/// A Linux distribution, as described by `os-release`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Distribution {
+    /// The distribution identifier, like `ubuntu` or `alpine`.
+    pub id: String,
+    /// The distribution version, like `22.04`.
+    pub version_id: Option<String>,
+    /// The name of the distribution for displaying.
+    pub pretty_name: Option<String>,
+}
+
+/// The C library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Libc {
+    /// The C library flavour.
+    pub kind: LibcKind,
+    /// The C library version, if known.
+    pub version: Option<String>,
+}
+
+/// Obtain the free space available to the unprivileged users on
+/// the file system the path is at, in bytes.
+///
+/// The path does not have to exist yet; its closest existing ancestor is
+/// checked then.
+pub fn free_space(path: &std::path::Path) -> std::io::Result<u64> {
+    let path = std::path::absolute(path)?;
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(&path);
+    self::implementation::free_space(existing)
 }
 
 /// Obtain the system information.
 pub fn detect() -> std::io::Result<Info> {
-    self::implementation::detect()
+    let info = self::implementation::detect()?;
+    Ok(Info {
+        arch: humanode_distribution_platform::arch(&info.arch).into_owned(),
+        platform: humanode_distribution_platform::platform(&info.platform).into_owned(),
+        translated_arches: info
+            .translated_arches
+            .iter()
+            .map(|arch| humanode_distribution_platform::arch(arch).into_owned())
+            .collect(),
+        ..info
+    })
 }
@@ -0,0 +1,89 @@
+//! The `os-release` file parsing.
+
+use crate::Distribution;
+
+/// The locations of the `os-release` file, in the order of precedence.
+const PATHS: &[&str] = &["/etc/os-release", "/usr/lib/os-release"];
+
+/// Read the distribution info from the `os-release` file.
+pub fn read() -> Option<Distribution> {
+    PATHS
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| parse(&contents))
+}
+
+/// Parse the `os-release` file contents.
+///
+/// Returns `None` if the distribution identifier is missing.
+pub fn parse(contents: &str) -> Option<Distribution> {
+    let mut id = None;
+    let mut version_id = None;
+    let mut pretty_name = None;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = unquote(value.trim()).to_owned();
+        match key.trim() {
+            "ID" => id = Some(value),
+            "VERSION_ID" => version_id = Some(value),
+            "PRETTY_NAME" => pretty_name = Some(value),
+            _ => {}
+        }
+    }
+
+    Some(Distribution {
+        id: id?,
+        version_id,
+        pretty_name,
+    })
+}
+
+/// Strip the matching quotes around the value, if any.
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_os_release() {
+        let contents = r#"
+# A comment.
+NAME="Ubuntu"
+VERSION_ID="22.04"
+ID=ubuntu
+ID_LIKE=debian
+PRETTY_NAME='Ubuntu 22.04.4 LTS'
+"#;
+        assert_eq!(
+            parse(contents),
+            Some(Distribution {
+                id: "ubuntu".into(),
+                version_id: Some("22.04".into()),
+                pretty_name: Some("Ubuntu 22.04.4 LTS".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn requires_id() {
+        assert_eq!(parse("NAME=Linux\n"), None);
+    }
+}
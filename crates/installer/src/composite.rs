@@ -0,0 +1,178 @@
+//! Installation of a package along with its dependencies.
+
+use std::path::{Path, PathBuf};
+
+use futures::{pin_mut, Sink};
+use humanode_distribution_schema::manifest::Package;
+use humanode_distribution_transport::{retry, Transport};
+
+use crate::{
+    install::{self, InstallationError, Installed},
+    progress::Event,
+    rate,
+};
+
+/// A package to install as a part of the composite installation.
+pub struct Part {
+    /// The sub path of the installation directory to install the package
+    /// to; the package is installed right into the directory if not set.
+    pub sub_path: Option<String>,
+    /// The base URL to use for resolving the URLs.
+    pub base_url: String,
+    /// The alternate base URLs to load the files from.
+    pub mirror_urls: Vec<String>,
+    /// The package to install.
+    pub package: Package,
+}
+
+/// The composite installation routine parameters.
+pub struct Params<T> {
+    /// The transport to load the files with.
+    pub transport: T,
+    /// The retry policy for the transient download failures.
+    pub retry_policy: retry::Policy,
+    /// The path to the target directory where to install the packages.
+    pub dir: PathBuf,
+    /// The packages to install, in order, so the dependencies must go
+    /// before their dependents.
+    pub parts: Vec<Part>,
+    /// The download rate limiter, shared by all the packages.
+    pub rate_limiter: Option<rate::Limiter>,
+}
+
+/// An error that can happen during the composite installation.
+#[derive(Debug, thiserror::Error)]
+#[error("unable to install {display_name}: {error}")]
+pub struct CompositeInstallationError {
+    /// The display name of the package that failed to install.
+    pub display_name: String,
+    /// The underlying error.
+    #[source]
+    pub error: InstallationError,
+}
+
+/// Install the packages one by one, in order.
+///
+/// The installation stops at the first package that fails to install,
+/// leaving the packages installed before it in place.
+///
+/// The progress of every package is reported to the events sink in turn.
+pub async fn install<T: Transport>(
+    params: Params<T>,
+    events: impl Sink<Event>,
+) -> Result<Vec<Installed>, CompositeInstallationError> {
+    let Params {
+        transport,
+        retry_policy,
+        dir,
+        parts,
+        rate_limiter,
+    } = params;
+
+    pin_mut!(events);
+
+    let mut installed = Vec::with_capacity(parts.len());
+    for part in parts {
+        let Part {
+            sub_path,
+            base_url,
+            mirror_urls,
+            package,
+        } = part;
+
+        let dir = part_dir(&dir, sub_path.as_deref());
+        let display_name = package.display_name.clone();
+        let params = install::Params {
+            transport: &transport,
+            retry_policy: retry_policy.clone(),
+            dir,
+            base_url,
+            mirror_urls,
+            package,
+            rate_limiter: rate_limiter.clone(),
+        };
+        let part_installed = install::install(params, events.as_mut())
+            .await
+            .map_err(|error| CompositeInstallationError {
+                display_name,
+                error,
+            })?;
+        installed.push(part_installed);
+    }
+
+    Ok(installed)
+}
+
+/// The directory to install a part to: the sub path of the installation
+/// directory, or the installation directory itself if there is none.
+pub fn part_dir(dir: &Path, sub_path: Option<&str>) -> PathBuf {
+    match sub_path {
+        Some(sub_path) => dir.join(sub_path),
+        None => dir.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use digest::Digest;
+    use humanode_distribution_schema::{
+        manifest::{File, LocalPath, Sha256, Url},
+        test_utils,
+    };
+    use humanode_distribution_transport::memory::Memory;
+
+    use super::*;
+
+    /// A package with a single executable at the given URL, which all
+    /// the executable paths point to.
+    fn package(display_name: &str, sub_url: &str, contents: &[u8]) -> Package {
+        let executable = || LocalPath("bin/run".into());
+        Package {
+            executable_path: executable(),
+            ngrok_path: executable(),
+            humanode_websocket_tunnel_client_path: executable(),
+            files: vec![File {
+                sub_url: Url(sub_url.into()),
+                destination_sub_path: executable(),
+                sha256: Some(Sha256(hex::encode(sha2::Sha256::digest(contents)))),
+                sha512: None,
+                blake3: None,
+                size: None,
+            }],
+            ..test_utils::package(display_name)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn installs_parts_into_sub_paths() {
+        let transport = Memory::default()
+            .with("https://example.com/tunnel", "tunnel")
+            .with("https://example.com/peer", "peer");
+
+        let part = |sub_path: Option<&str>, package| Part {
+            sub_path: sub_path.map(Into::into),
+            base_url: "https://example.com/manifest.json".into(),
+            mirror_urls: Vec::new(),
+            package,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let params = Params {
+            transport,
+            retry_policy: retry::Policy::no_retries(),
+            dir: dir.path().into(),
+            parts: vec![
+                part(Some("tunnel"), package("Tunnel", "/tunnel", b"tunnel")),
+                part(None, package("Peer", "/peer", b"peer")),
+                part(Some("broken"), package("Broken", "/broken", b"broken")),
+            ],
+            rate_limiter: None,
+        };
+        let error = install(params, futures::sink::drain()).await.unwrap_err();
+        assert_eq!(error.display_name, "Broken");
+
+        let tunnel = std::fs::read(dir.path().join("tunnel/bin/run")).unwrap();
+        assert_eq!(tunnel, b"tunnel");
+        let peer = std::fs::read(dir.path().join("bin/run")).unwrap();
+        assert_eq!(peer, b"peer");
+    }
+}
@@ -0,0 +1,143 @@
+//! The file hashes.
+
+use digest::Digest;
+use humanode_distribution_schema::manifest::File;
+
+/// The hash algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// SHA-256.
+    Sha256,
+    /// SHA-512.
+    Sha512,
+    /// BLAKE3.
+    Blake3,
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Sha256 => "SHA-256",
+            Self::Sha512 => "SHA-512",
+            Self::Blake3 => "BLAKE3",
+        })
+    }
+}
+
+/// The hashes declared for the file, in hexadecimal representation.
+pub fn declared(file: &File) -> Vec<(Algorithm, &str)> {
+    let File {
+        sha256,
+        sha512,
+        blake3,
+        ..
+    } = file;
+
+    [
+        (
+            Algorithm::Sha256,
+            sha256.as_ref().map(|hash| hash.0.as_str()),
+        ),
+        (
+            Algorithm::Sha512,
+            sha512.as_ref().map(|hash| hash.0.as_str()),
+        ),
+        (
+            Algorithm::Blake3,
+            blake3.as_ref().map(|hash| hash.0.as_str()),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(algorithm, hash)| Some((algorithm, hash?)))
+    .collect()
+}
+
+/// The state of a single hash computation.
+enum State {
+    /// SHA-256.
+    Sha256(sha2::Sha256),
+    /// SHA-512.
+    Sha512(sha2::Sha512),
+    /// BLAKE3.
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// Computes the hashes of multiple algorithms in one pass over the data.
+pub struct Hasher {
+    /// The computations in progress.
+    states: Vec<(Algorithm, State)>,
+}
+
+impl Hasher {
+    /// Create a new hasher for the given algorithms.
+    pub fn new(algorithms: impl IntoIterator<Item = Algorithm>) -> Self {
+        let states = algorithms
+            .into_iter()
+            .map(|algorithm| {
+                let state = match algorithm {
+                    Algorithm::Sha256 => State::Sha256(sha2::Sha256::new()),
+                    Algorithm::Sha512 => State::Sha512(sha2::Sha512::new()),
+                    Algorithm::Blake3 => State::Blake3(Box::default()),
+                };
+                (algorithm, state)
+            })
+            .collect();
+        Self { states }
+    }
+
+    /// Finish the computations and return the hashes.
+    pub fn finalize(self) -> Vec<(Algorithm, Vec<u8>)> {
+        self.states
+            .into_iter()
+            .map(|(algorithm, state)| {
+                let hash = match state {
+                    State::Sha256(state) => state.finalize().to_vec(),
+                    State::Sha512(state) => state.finalize().to_vec(),
+                    State::Blake3(state) => state.finalize().as_bytes().to_vec(),
+                };
+                (algorithm, hash)
+            })
+            .collect()
+    }
+}
+
+impl digest::Update for Hasher {
+    fn update(&mut self, data: &[u8]) {
+        for (_, state) in &mut self.states {
+            match state {
+                State::Sha256(state) => Digest::update(state, data),
+                State::Sha512(state) => Digest::update(state, data),
+                State::Blake3(state) => {
+                    state.update(data);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_all_hashes() {
+        let mut hasher = Hasher::new([Algorithm::Sha512, Algorithm::Blake3]);
+        digest::Update::update(&mut hasher, b"hello ");
+        digest::Update::update(&mut hasher, b"world");
+
+        let hashes = hasher.finalize();
+        assert_eq!(
+            hashes,
+            [
+                (
+                    Algorithm::Sha512,
+                    sha2::Sha512::digest(b"hello world").to_vec()
+                ),
+                (
+                    Algorithm::Blake3,
+                    blake3::hash(b"hello world").as_bytes().to_vec()
+                ),
+            ]
+        );
+    }
+}
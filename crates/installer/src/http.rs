@@ -1,61 +1,100 @@
 //! The HTTP utils.
 
-use digest::Digest;
-use futures::pin_mut;
+use digest::Update;
+use futures::{pin_mut, Sink, SinkExt, TryStreamExt};
+use humanode_distribution_transport::{
+    retry::{Classify, Verdict},
+    Transport,
+};
 use tokio::io::AsyncWriteExt;
 
+use crate::rate::Limiter;
+
 /// An error that can happen when the loading a file.
 #[derive(Debug, thiserror::Error)]
 pub enum FileLoadError {
-    /// A failure from `reqwest`.
-    #[error("reqwest error: {0}")]
-    Reqwest(#[source] reqwest::Error),
-    /// The server returned a bad status code.
-    #[error("server error: {0}")]
-    Server(reqwest::StatusCode),
-    /// The reading from the network failed.
+    /// A failure from the transport when starting the transfer.
+    #[error(transparent)]
+    Transport(humanode_distribution_transport::Error),
+    /// The reading of the transferred data failed.
     #[error("read error: {0}")]
-    Read(#[source] reqwest::Error),
+    Read(#[source] humanode_distribution_transport::Error),
     /// The writing to the file failed.
     #[error("write error: {0}")]
     Write(#[source] tokio::io::Error),
+    /// More data was transferred than the size limit allows.
+    #[error("transferred {received} bytes, more than the {limit} bytes limit")]
+    TooLarge {
+        /// The size limit.
+        limit: u64,
+        /// The number of bytes received by the time the transfer was aborted.
+        received: u64,
+    },
+}
+
+impl Classify for FileLoadError {
+    fn classify(&self) -> Verdict {
+        match self {
+            Self::Transport(error) | Self::Read(error) => error.classify(),
+            Self::Write(_) | Self::TooLarge { .. } => Verdict::Fatal,
+        }
+    }
+}
+
+/// A loaded file.
+#[derive(Debug)]
+pub struct Loaded<T> {
+    /// The digest fed with the file contents.
+    pub digest: T,
+    /// The size of the file, in bytes.
+    pub size: u64,
 }
 
-/// Load a meta URL and parse it as a YAML document.
-pub async fn load_file<T: Digest>(
-    client: &reqwest::Client,
+/// Load a file from the URL into the destination, feeding its contents to
+/// the digest.
+///
+/// The transfer is aborted as soon as it exceeds the size limit, if any.
+/// The number of bytes received so far is reported to the progress sink
+/// after every chunk. The transfer is slowed down to the limiter rate, if any.
+pub async fn load_file<T: Update>(
+    transport: &impl Transport,
     url: &str,
     dest: impl tokio::io::AsyncWrite,
     mut digest: T,
-) -> Result<digest::Output<T>, FileLoadError> {
-    let req = client.get(url).build().map_err(FileLoadError::Reqwest)?;
-
-    let mut res = client.execute(req).await.map_err(FileLoadError::Reqwest)?;
-
-    let status = res.status();
-    if !status.is_success() {
-        return Err(FileLoadError::Server(status));
-    }
+    limit: Option<u64>,
+    limiter: Option<&Limiter>,
+    progress: impl Sink<u64>,
+) -> Result<Loaded<T>, FileLoadError> {
+    let mut stream = transport
+        .stream(url)
+        .await
+        .map_err(FileLoadError::Transport)?;
 
     pin_mut!(dest);
+    pin_mut!(progress);
 
-    while let Some(mut buf) = res.chunk().await.map_err(FileLoadError::Read)? {
-        use bytes::Buf;
-        loop {
-            let chunk = buf.chunk();
-            if chunk.is_empty() {
-                break;
+    let mut size: u64 = 0;
+    while let Some(chunk) = stream.try_next().await.map_err(FileLoadError::Read)? {
+        size = size.saturating_add(chunk.len().try_into().unwrap_or(u64::MAX));
+        if let Some(limit) = limit {
+            if size > limit {
+                return Err(FileLoadError::TooLarge {
+                    limit,
+                    received: size,
+                });
             }
-
-            dest.write_all(chunk).await.map_err(FileLoadError::Write)?;
-            tokio::task::block_in_place(|| {
-                digest.update(chunk);
-            });
-
-            buf.advance(chunk.len());
         }
+        if let Some(limiter) = limiter {
+            limiter
+                .consume(chunk.len().try_into().unwrap_or(u64::MAX))
+                .await;
+        }
+        dest.write_all(&chunk).await.map_err(FileLoadError::Write)?;
+        tokio::task::block_in_place(|| {
+            digest.update(&chunk);
+        });
+        let _ = progress.send(size).await;
     }
 
-    let hash = digest.finalize();
-    Ok(hash)
+    Ok(Loaded { digest, size })
 }
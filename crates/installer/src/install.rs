@@ -1,19 +1,44 @@
 //! Installation logic.
 
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    pin::Pin,
+    time::Instant,
+};
 
-use digest::Digest;
+use futures::{lock::Mutex, pin_mut, Sink, SinkExt};
 use humanode_distribution_schema::manifest::Package;
+use humanode_distribution_transport::{
+    file,
+    retry::{self, Classify, Verdict},
+    Transport,
+};
 use url::Url;
 
-use crate::http::{self, FileLoadError};
+use crate::{
+    hash::{self, Algorithm},
+    http::{self, FileLoadError},
+    progress::Event,
+    rate,
+    receipt::{self, Receipt, ReceiptError},
+};
 
 /// An error that can happen during the installation.
 #[derive(Debug, thiserror::Error)]
 pub enum InstallationError {
-    /// Base URL is invalid.
-    #[error("unable to parse hash {hash:?} for a given file {url}: {error}")]
+    /// The file does not declare any hash.
+    #[error("no hash is declared for a given file {url}")]
+    NoHash {
+        /// The path of the file.
+        path: String,
+        /// The URL of the file.
+        url: String,
+    },
+    /// A hash declared for a file is invalid.
+    #[error("unable to parse {algorithm} hash {hash:?} for a given file {url}: {error}")]
     ParseHash {
+        /// The algorithm of the hash.
+        algorithm: Algorithm,
         /// The hash that we attempted to parse.
         hash: String,
         /// The path of the file we attempted to parse the hash for.
@@ -33,6 +58,17 @@ pub enum InstallationError {
         #[source]
         error: url::ParseError,
     },
+    /// Failed to parse the mirror URL relative to the base URL.
+    #[error("invalid mirror URL {url}: {error}")]
+    ParseMirrorUrl {
+        /// The URL we attempted to parse.
+        url: String,
+        /// The base URL we used.
+        base_url: Url,
+        /// The underlying error.
+        #[source]
+        error: <Url as std::str::FromStr>::Err,
+    },
     /// Failed to parse the file URL relative to the base URL.
     #[error("invalid file URL {url}: {error}")]
     ParseFileUrl {
@@ -44,6 +80,14 @@ pub enum InstallationError {
         #[source]
         error: <Url as std::str::FromStr>::Err,
     },
+    /// The remote manifest refers to a local file.
+    #[error("the remote manifest {manifest_url} can not refer to the local {url}")]
+    LocalUrl {
+        /// The URL of the local file.
+        url: String,
+        /// The URL of the manifest.
+        manifest_url: Url,
+    },
     /// Failed to create a dir where to put the files.
     #[error("unable to create dir {path}: {error}")]
     CreateDir {
@@ -74,15 +118,30 @@ pub enum InstallationError {
         error: FileLoadError,
     },
     /// The file hash did not match the expectation.
-    #[error("loaded file {path} hash mismatch: expected {expected:?} but got {actual:?}")]
+    #[error(
+        "loaded file {path} {algorithm} hash mismatch: expected {expected:?} but got {actual:?}"
+    )]
     FileHashMismatch {
         /// The file path.
         path: PathBuf,
+        /// The algorithm of the mismatched hash.
+        algorithm: Algorithm,
         /// The expected hash.
         expected: Vec<u8>,
         /// The actual hash.
         actual: Vec<u8>,
     },
+    /// The file size did not match the expectation.
+    #[error("loaded file {path} size mismatch: expected {expected} bytes but got {actual}")]
+    SizeMismatch {
+        /// The file path.
+        path: PathBuf,
+        /// The expected size.
+        expected: u64,
+        /// The actual size, or the number of bytes received until the transfer
+        /// was aborted for exceeding the expected size.
+        actual: u64,
+    },
     /// Failed to set the file permissions.
     #[error("unable to set permissions for {path}: {error}")]
     SetFilePermissions {
@@ -92,56 +151,162 @@ pub enum InstallationError {
         #[source]
         error: std::io::Error,
     },
+    /// Failed to write the install receipt.
+    #[error("unable to write the install receipt: {error}")]
+    WriteReceipt {
+        /// The underlying error.
+        #[source]
+        error: ReceiptError,
+    },
+}
+
+impl InstallationError {
+    /// Whether the error is caused by the source the file was loaded from,
+    /// meaning loading the file from another source might succeed.
+    fn is_source_failure(&self) -> bool {
+        match self {
+            Self::ParseFileUrl { .. }
+            | Self::FileHashMismatch { .. }
+            | Self::SizeMismatch { .. } => true,
+            Self::LoadFile { error, .. } => !matches!(error, FileLoadError::Write(_)),
+            _ => false,
+        }
+    }
+}
+
+impl Classify for InstallationError {
+    fn classify(&self) -> Verdict {
+        match self {
+            Self::LoadFile { error, .. } => error.classify(),
+            _ => Verdict::Fatal,
+        }
+    }
 }
 
 /// The installation routine parameters.
-pub struct Params {
-    /// HTTP client.
-    pub client: reqwest::Client,
+pub struct Params<T> {
+    /// The transport to load the files with.
+    pub transport: T,
+    /// The retry policy for the transient download failures.
+    pub retry_policy: retry::Policy,
     /// The path to the target directory where to install the package.
-    pub dir: String,
+    pub dir: PathBuf,
     /// The base URL to use for resolving the URLs.
     pub base_url: String,
+    /// The alternate base URLs to load the files from, tried in order if
+    /// loading from the base URL fails.
+    ///
+    /// Relative mirror URLs are resolved against the base URL.
+    /// The `file://` mirror and file URLs are only allowed if the base URL
+    /// is a `file://` one too.
+    pub mirror_urls: Vec<String>,
     /// The package to install.
     pub package: Package,
+    /// The download rate limiter, if the downloads are to be limited.
+    pub rate_limiter: Option<rate::Limiter>,
+}
+
+/// The outcome of a successful installation.
+#[derive(Debug)]
+pub struct Installed {
+    /// The files installed, in order.
+    pub files: Vec<InstalledFile>,
+}
+
+/// A single installed file.
+#[derive(Debug)]
+pub struct InstalledFile {
+    /// The path to the file.
+    pub path: PathBuf,
+    /// The size of the file in bytes.
+    pub size: u64,
 }
 
-/// Prepare the directories, then download the files and set proper file
-/// permissions.
-pub async fn install(params: Params) -> Result<(), InstallationError> {
+/// Prepare the directories, then download the files, set proper file
+/// permissions and write the install receipt.
+///
+/// The progress is reported to the events sink as the installation goes.
+pub async fn install<T: Transport>(
+    params: Params<T>,
+    events: impl Sink<Event>,
+) -> Result<Installed, InstallationError> {
     let Params {
-        client,
-        dir,
+        transport,
+        retry_policy,
+        dir: base_path,
         base_url,
+        mirror_urls,
         package,
+        rate_limiter,
     } = params;
 
-    let base_path = PathBuf::from(dir);
     let base_url = Url::parse(&base_url).map_err(|error| InstallationError::ParseBaseUrl {
         url: base_url,
         error,
     })?;
 
+    let mut base_urls = vec![base_url.clone()];
+    for mirror_url in mirror_urls {
+        let mirror_url = Url::options()
+            .base_url(Some(&base_url))
+            .parse(&mirror_url)
+            .map_err(|error| InstallationError::ParseMirrorUrl {
+                url: mirror_url.clone(),
+                base_url: base_url.clone(),
+                error,
+            })?;
+        if !file::may_refer(base_url.as_str(), mirror_url.as_str()) {
+            return Err(InstallationError::LocalUrl {
+                url: mirror_url.into(),
+                manifest_url: base_url,
+            });
+        }
+        base_urls.push(mirror_url);
+    }
+
+    let receipt = Receipt::new(&package, base_url.to_string());
+
+    pin_mut!(events);
+    let reporter = Reporter {
+        events: Mutex::new(events),
+    };
+
+    let total_bytes = package
+        .files
+        .iter()
+        .try_fold(0u64, |total, file| Some(total.saturating_add(file.size?)));
+    reporter
+        .event(Event::Started {
+            files: package.files.len(),
+            total_bytes,
+        })
+        .await;
+
     // Download the files.
+    let mut installed = Vec::with_capacity(package.files.len());
     for file in package.files {
         let path = base_path.join(&file.destination_sub_path.0);
 
-        let expected_hash =
-            hex::decode(&file.sha256.0).map_err(|error| InstallationError::ParseHash {
-                hash: file.sha256.0.clone(),
+        let declared = hash::declared(&file);
+        if declared.is_empty() {
+            return Err(InstallationError::NoHash {
                 path: file.destination_sub_path.0.clone(),
                 url: file.sub_url.0.clone(),
-                error,
-            })?;
-
-        let url = Url::options()
-            .base_url(Some(&base_url))
-            .parse(&file.sub_url.0)
-            .map_err(|error| InstallationError::ParseFileUrl {
-                url: file.sub_url.0.clone(),
-                base_url: base_url.clone(),
-                error,
-            })?;
+            });
+        }
+        let expected_hashes = declared
+            .into_iter()
+            .map(|(algorithm, hash)| {
+                let decoded = hex::decode(hash).map_err(|error| InstallationError::ParseHash {
+                    algorithm,
+                    hash: hash.to_owned(),
+                    path: file.destination_sub_path.0.clone(),
+                    url: file.sub_url.0.clone(),
+                    error,
+                })?;
+                Ok((algorithm, decoded))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         if let Some(parent) = path.parent() {
             tokio::fs::create_dir_all(&parent).await.map_err(|error| {
@@ -152,33 +317,41 @@ pub async fn install(params: Params) -> Result<(), InstallationError> {
             })?;
         }
 
-        let fileio = tokio::fs::OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(&path)
-            .await
-            .map_err(|error| InstallationError::CreateFile {
-                path: path.clone(),
-                error,
-            })?;
-        let digest = sha2::Sha256::new();
-        let hash = http::load_file(&client, url.as_str(), fileio, digest)
-            .await
-            .map_err(|error| InstallationError::LoadFile {
-                path: path.clone(),
-                url: url.to_string(),
-                error,
-            })?;
-
-        #[allow(deprecated)]
-        if hash.as_slice() != expected_hash.as_slice() {
-            return Err(InstallationError::FileHashMismatch {
-                path: path.clone(),
-                expected: expected_hash,
-                actual: hash.to_vec(),
-            });
+        // The integrity is guaranteed by the hash regardless of which source
+        // served the file, so just go over the sources until one succeeds.
+        let started_at = Instant::now();
+        let download = Download {
+            manifest_url: &base_urls[0],
+            sub_url: &file.sub_url.0,
+            path: &path,
+            expected_hashes: &expected_hashes,
+            expected_size: file.size,
+        };
+        let mut result = Ok(0);
+        for base_url in &base_urls {
+            result = load_file(
+                &transport,
+                &retry_policy,
+                rate_limiter.as_ref(),
+                &reporter,
+                base_url,
+                &download,
+            )
+            .await;
+            match result {
+                Err(ref error) if error.is_source_failure() => continue,
+                _ => break,
+            }
         }
+        let size = result?;
+        reporter
+            .event(Event::FileVerified {
+                path: path.clone(),
+                size,
+                duration: started_at.elapsed(),
+            })
+            .await;
+        installed.push(InstalledFile { path, size });
     }
 
     // Set executable permissions, only on unix systems.
@@ -195,9 +368,455 @@ pub async fn install(params: Params) -> Result<(), InstallationError> {
             use std::{fs::Permissions, os::unix::prelude::PermissionsExt};
             tokio::fs::set_permissions(&path, Permissions::from_mode(0o755))
                 .await
-                .map_err(|error| InstallationError::SetFilePermissions { path, error })?;
+                .map_err(|error| InstallationError::SetFilePermissions {
+                    path: path.clone(),
+                    error,
+                })?;
+            reporter.event(Event::PermissionsSet { path }).await;
+        }
+    }
+
+    tokio::fs::create_dir_all(&base_path)
+        .await
+        .map_err(|error| InstallationError::CreateDir {
+            path: base_path.clone(),
+            error,
+        })?;
+    receipt::write(&base_path, &receipt)
+        .await
+        .map_err(|error| InstallationError::WriteReceipt { error })?;
+
+    Ok(Installed { files: installed })
+}
+
+/// The sink to report the progress to.
+///
+/// The sink is behind a lock so that the retried operations can share it.
+struct Reporter<'a, E> {
+    /// The progress events sink.
+    events: Mutex<Pin<&'a mut E>>,
+}
+
+impl<E: Sink<Event>> Reporter<'_, E> {
+    /// Report a progress event.
+    async fn event(&self, event: Event) {
+        let _ = self.events.lock().await.send(event).await;
+    }
+}
+
+/// A file to download, the same for all the sources.
+struct Download<'a> {
+    /// The URL of the manifest the file is declared by.
+    manifest_url: &'a Url,
+    /// The URL of the file, relative to the source.
+    sub_url: &'a str,
+    /// The local path to write the file to.
+    path: &'a Path,
+    /// The hashes the file must have.
+    expected_hashes: &'a [(Algorithm, Vec<u8>)],
+    /// The size the file must have, if known.
+    expected_size: Option<u64>,
+}
+
+/// Download a single file from the given source and check its hashes.
+///
+/// Returns the size of the file.
+async fn load_file<E: Sink<Event>>(
+    transport: &impl Transport,
+    retry_policy: &retry::Policy,
+    rate_limiter: Option<&rate::Limiter>,
+    reporter: &Reporter<'_, E>,
+    base_url: &Url,
+    download: &Download<'_>,
+) -> Result<u64, InstallationError> {
+    let &Download {
+        manifest_url,
+        sub_url,
+        path,
+        expected_hashes,
+        expected_size,
+    } = download;
+
+    let url = Url::options()
+        .base_url(Some(base_url))
+        .parse(sub_url)
+        .map_err(|error| InstallationError::ParseFileUrl {
+            url: sub_url.to_owned(),
+            base_url: base_url.clone(),
+            error,
+        })?;
+    if !file::may_refer(manifest_url.as_str(), url.as_str()) {
+        return Err(InstallationError::LocalUrl {
+            url: url.into(),
+            manifest_url: manifest_url.clone(),
+        });
+    }
+
+    // Every attempt starts over with a truncated file.
+    let loaded = retry::retry(retry_policy, || async {
+        let fileio = tokio::fs::OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(path)
+            .await
+            .map_err(|error| InstallationError::CreateFile {
+                path: path.to_path_buf(),
+                error,
+            })?;
+        reporter
+            .event(Event::FileStarted {
+                path: path.to_path_buf(),
+                url: url.to_string(),
+                size: expected_size,
+            })
+            .await;
+        let progress = futures::sink::unfold((), |(), received| async move {
+            reporter
+                .event(Event::FileProgress {
+                    path: path.to_path_buf(),
+                    received,
+                    size: expected_size,
+                })
+                .await;
+            Ok::<_, std::convert::Infallible>(())
+        });
+        let digest = hash::Hasher::new(expected_hashes.iter().map(|(algorithm, _)| *algorithm));
+        http::load_file(
+            transport,
+            url.as_str(),
+            fileio,
+            digest,
+            expected_size,
+            rate_limiter,
+            progress,
+        )
+        .await
+        .map_err(|error| match error {
+            FileLoadError::TooLarge { limit, received } => InstallationError::SizeMismatch {
+                path: path.to_path_buf(),
+                expected: limit,
+                actual: received,
+            },
+            error => InstallationError::LoadFile {
+                path: path.to_path_buf(),
+                url: url.to_string(),
+                error,
+            },
+        })
+    })
+    .await?;
+
+    if let Some(expected) = expected_size {
+        if loaded.size != expected {
+            return Err(InstallationError::SizeMismatch {
+                path: path.to_path_buf(),
+                expected,
+                actual: loaded.size,
+            });
+        }
+    }
+
+    let actual_hashes = loaded.digest.finalize();
+    for ((algorithm, expected), (_, actual)) in expected_hashes.iter().zip(actual_hashes) {
+        if &actual != expected {
+            return Err(InstallationError::FileHashMismatch {
+                path: path.to_path_buf(),
+                algorithm: *algorithm,
+                expected: expected.clone(),
+                actual,
+            });
+        }
+    }
+
+    Ok(loaded.size)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use digest::Digest;
+    use humanode_distribution_schema::{
+        manifest::{Blake3, File, LocalPath, Sha256, Url as SubUrl},
+        test_utils,
+    };
+    use humanode_distribution_transport::{http::Http, memory::Memory, Error};
+    use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    /// The file contents served by the mock server.
+    const CONTENTS: &[u8] = b"#!/bin/sh\n";
+
+    /// A package with a single file, which all the executable paths
+    /// point to.
+    fn package() -> Package {
+        let executable = || LocalPath("bin/peer".into());
+        Package {
+            executable_path: executable(),
+            ngrok_path: executable(),
+            humanode_websocket_tunnel_client_path: executable(),
+            files: vec![File {
+                sub_url: SubUrl("/files/peer".into()),
+                destination_sub_path: executable(),
+                sha256: Some(Sha256(hex::encode(sha2::Sha256::digest(CONTENTS)))),
+                sha512: None,
+                blake3: Some(Blake3(blake3::hash(CONTENTS).to_hex().to_string())),
+                size: Some(CONTENTS.len().try_into().unwrap()),
+            }],
+            ..test_utils::package("Test")
+        }
+    }
+
+    /// The URL of the manifest the in-memory files are relative to.
+    const MANIFEST_URL: &str = "https://example.com/manifest.json";
+
+    /// The installation params for the package declared by the manifest at
+    /// the given URL.
+    fn params<T>(transport: T, base_url: &str, dir: &tempfile::TempDir) -> Params<T> {
+        Params {
+            transport,
+            retry_policy: retry::Policy {
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+            },
+            dir: dir.path().into(),
+            base_url: base_url.into(),
+            mirror_urls: Vec::new(),
+            package: package(),
+            rate_limiter: None,
+        }
+    }
+
+    /// The installation params against the mock server.
+    fn server_params(server: &MockServer, dir: &tempfile::TempDir) -> Params<Http> {
+        params(
+            Http::default(),
+            &format!("{}/manifest.json", server.uri()),
+            dir,
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn retries_transient_download_failures() {
+        let server = MockServer::start().await;
+        Mock::given(path("/files/peer"))
+            .respond_with(ResponseTemplate::new(502).set_body_bytes("partial"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(path("/files/peer"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(CONTENTS))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        install(server_params(&server, &dir), futures::sink::drain())
+            .await
+            .unwrap();
+
+        let installed = std::fs::read(dir.path().join("bin/peer")).unwrap();
+        assert_eq!(installed, CONTENTS);
+
+        let receipt = receipt::read(dir.path()).await.unwrap().unwrap();
+        assert_eq!(receipt.display_name, "Test");
+        assert_eq!(
+            receipt.manifest_url,
+            format!("{}/manifest.json", server.uri())
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn does_not_retry_missing_files() {
+        let server = MockServer::start().await;
+        Mock::given(path("/files/peer"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let error = install(server_params(&server, &dir), futures::sink::drain())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            InstallationError::LoadFile {
+                error: FileLoadError::Transport(Error::Server { status, .. }),
+                ..
+            } if status == reqwest::StatusCode::NOT_FOUND
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn fails_over_to_mirrors() {
+        let primary = MockServer::start().await;
+        Mock::given(path("/files/peer"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&primary)
+            .await;
+        let corrupted = MockServer::start().await;
+        Mock::given(path("/files/peer"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes("corrupted"))
+            .expect(1)
+            .mount(&corrupted)
+            .await;
+        let mirror = MockServer::start().await;
+        Mock::given(path("/files/peer"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(CONTENTS))
+            .expect(1)
+            .mount(&mirror)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let params = Params {
+            mirror_urls: vec![corrupted.uri(), mirror.uri()],
+            ..server_params(&primary, &dir)
+        };
+        install(params, futures::sink::drain()).await.unwrap();
+
+        let installed = std::fs::read(dir.path().join("bin/peer")).unwrap();
+        assert_eq!(installed, CONTENTS);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn rejects_hash_mismatch() {
+        let transport = Memory::default()
+            .with("https://example.com/files/peer", "corrupted!")
+            .with("https://mirror.example.com/files/peer", "corrupted?");
+
+        let dir = tempfile::tempdir().unwrap();
+        let params = Params {
+            mirror_urls: vec!["https://mirror.example.com/".into()],
+            ..params(transport.clone(), MANIFEST_URL, &dir)
+        };
+        let error = install(params, futures::sink::drain()).await.unwrap_err();
+
+        assert!(matches!(error, InstallationError::FileHashMismatch { .. }));
+        assert_eq!(
+            transport.requests(),
+            [
+                "https://example.com/files/peer",
+                "https://mirror.example.com/files/peer"
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn keeps_remote_manifests_off_local_files() {
+        let transport = Memory::default().with("file:///srv/files/peer", CONTENTS);
+        let dir = tempfile::tempdir().unwrap();
+        let params = |base_url: &str, mirror_urls: &[&str], package| Params {
+            mirror_urls: mirror_urls.iter().map(|&url| url.into()).collect(),
+            package,
+            ..params(transport.clone(), base_url, &dir)
+        };
+        let mut local = package();
+        local.files[0].sub_url = SubUrl("file:///srv/files/peer".into());
+
+        let cases = [
+            params("https://example.com/manifest.json", &[], local.clone()),
+            params(
+                "https://example.com/manifest.json",
+                &["file:///srv/"],
+                package(),
+            ),
+        ];
+        for params in cases {
+            let error = install(params, futures::sink::drain()).await.unwrap_err();
+            assert!(
+                matches!(error, InstallationError::LocalUrl { ref url, .. } if url.starts_with("file:///srv/")),
+                "{error}"
+            );
         }
+        assert!(transport.requests().is_empty());
+
+        let params = params("file:///srv/manifest.json", &[], local);
+        install(params, futures::sink::drain()).await.unwrap();
+        assert_eq!(transport.requests(), ["file:///srv/files/peer"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn aborts_oversized_downloads() {
+        let oversized = [CONTENTS, &[b'#'; 1024]].concat();
+        let transport = Memory::default().with("https://example.com/files/peer", oversized);
+
+        let dir = tempfile::tempdir().unwrap();
+        let params = params(transport, MANIFEST_URL, &dir);
+        let error = install(params, futures::sink::drain()).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            InstallationError::SizeMismatch { expected, actual, .. }
+                if expected == 10 && actual > expected
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn requires_all_hashes_to_match() {
+        let transport = Memory::default().with("https://example.com/files/peer", CONTENTS);
+
+        let mut package = package();
+        package.files[0].blake3 = Some(Blake3(blake3::hash(b"other").to_hex().to_string()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let params = Params {
+            package,
+            ..params(transport, MANIFEST_URL, &dir)
+        };
+        let error = install(params, futures::sink::drain()).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            InstallationError::FileHashMismatch {
+                algorithm: Algorithm::Blake3,
+                ..
+            }
+        ));
     }
 
-    Ok(())
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reports_progress() {
+        let transport = Memory::default().with("https://example.com/files/peer", CONTENTS);
+
+        let dir = tempfile::tempdir().unwrap();
+        let params = params(transport, MANIFEST_URL, &dir);
+        let mut events = Vec::new();
+        install(params, &mut events).await.unwrap();
+
+        let path = dir.path().join("bin/peer");
+        let events: Vec<String> = events
+            .into_iter()
+            .map(|event| match event {
+                Event::Started { files, total_bytes } => {
+                    format!("started {files} {total_bytes:?}")
+                }
+                Event::FileStarted { path: p, url, size } => {
+                    format!("file started {} {url} {size:?}", p == path)
+                }
+                Event::FileProgress {
+                    path: p, received, ..
+                } => format!("file progress {} {received}", p == path),
+                Event::FileVerified { path: p, size, .. } => {
+                    format!("file verified {} {size}", p == path)
+                }
+                Event::PermissionsSet { path: p } => format!("permissions set {}", p == path),
+            })
+            .collect();
+        let mut expected = vec![
+            "started 1 Some(10)",
+            "file started true https://example.com/files/peer Some(10)",
+            "file progress true 10",
+            "file verified true 10",
+        ];
+        if cfg!(unix) {
+            expected.extend(["permissions set true"; 3]);
+        }
+        assert_eq!(events, expected);
+    }
 }
@@ -1,4 +1,11 @@
 //! The installer logic.
 
+pub mod composite;
+pub mod hash;
 pub mod http;
 pub mod install;
+pub mod preflight;
+pub mod progress;
+pub mod rate;
+pub mod receipt;
+pub mod size;
@@ -0,0 +1,236 @@
+//! The checks to run before the installation.
+
+use humanode_distribution_schema::manifest::Package;
+
+use crate::size::Bytes;
+
+/// The resources available on the system.
+///
+/// The unknown values are not checked.
+#[derive(Debug, Default, Clone)]
+pub struct Available {
+    /// The free disk space at the installation directory, in bytes.
+    pub disk_space: Option<u64>,
+    /// The total physical memory, in bytes.
+    pub memory: Option<u64>,
+    /// The number of the CPU cores.
+    pub cpu_cores: Option<usize>,
+}
+
+/// A resource the system does not have enough of.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Shortfall {
+    /// Not enough free disk space.
+    #[error("not enough disk space: {} required, {} available", Bytes(*.required), Bytes(*.available))]
+    DiskSpace {
+        /// The required disk space, in bytes.
+        required: u64,
+        /// The available disk space, in bytes.
+        available: u64,
+    },
+    /// Not enough memory.
+    #[error("not enough memory: {} required, {} available", Bytes(*.required), Bytes(*.available))]
+    Memory {
+        /// The required memory, in bytes.
+        required: u64,
+        /// The available memory, in bytes.
+        available: u64,
+    },
+    /// Not enough CPU cores.
+    #[error("not enough CPU cores: {required} required, {available} available")]
+    CpuCores {
+        /// The required number of CPU cores.
+        required: u32,
+        /// The available number of CPU cores.
+        available: usize,
+    },
+}
+
+/// The system does not have the resources the package needs.
+#[derive(Debug, thiserror::Error)]
+#[error("the system does not meet the package requirements: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct PreflightError(pub Vec<Shortfall>);
+
+/// The disk space the package takes once installed, in bytes, if known.
+///
+/// Falls back to the sum of the file sizes if the package does not declare
+/// the disk space explicitly.
+pub fn disk_space(package: &Package) -> Option<u64> {
+    if let Some(disk_space) = package.resources.as_ref().and_then(|r| r.disk_space) {
+        return Some(disk_space);
+    }
+    package
+        .files
+        .iter()
+        .try_fold(0u64, |total, file| Some(total.saturating_add(file.size?)))
+}
+
+/// Check the available resources against the needs of the packages that
+/// are installed together.
+///
+/// The disk space the packages take adds up, with the unknown sizes left
+/// out, while the memory and the CPU cores must suffice for each package.
+pub fn check<'a>(
+    packages: impl IntoIterator<Item = &'a Package>,
+    available: &Available,
+) -> Result<(), PreflightError> {
+    let mut shortfalls = Vec::new();
+
+    let mut required_disk_space = 0u64;
+    let mut required_memory = None;
+    let mut required_cpu_cores = None;
+    for package in packages {
+        if let Some(disk_space) = disk_space(package) {
+            required_disk_space = required_disk_space.saturating_add(disk_space);
+        }
+        if let Some(resources) = &package.resources {
+            required_memory = required_memory.max(resources.min_memory);
+            required_cpu_cores = required_cpu_cores.max(resources.min_cpu_cores);
+        }
+    }
+
+    if let Some(available) = available.disk_space {
+        if available < required_disk_space {
+            shortfalls.push(Shortfall::DiskSpace {
+                required: required_disk_space,
+                available,
+            });
+        }
+    }
+    if let (Some(required), Some(available)) = (required_memory, available.memory) {
+        if available < required {
+            shortfalls.push(Shortfall::Memory {
+                required,
+                available,
+            });
+        }
+    }
+    if let (Some(required), Some(available)) = (required_cpu_cores, available.cpu_cores) {
+        if available < usize::try_from(required).unwrap_or(usize::MAX) {
+            shortfalls.push(Shortfall::CpuCores {
+                required,
+                available,
+            });
+        }
+    }
+
+    if shortfalls.is_empty() {
+        Ok(())
+    } else {
+        Err(PreflightError(shortfalls))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::{
+        manifest::{File, LocalPath, Resources, Sha256, Url},
+        test_utils,
+    };
+
+    use super::*;
+
+    /// A package with the files of given sizes.
+    fn package(sizes: &[Option<u64>], resources: Option<Resources>) -> Package {
+        Package {
+            files: sizes
+                .iter()
+                .enumerate()
+                .map(|(index, &size)| File {
+                    sub_url: Url(format!("/files/{index}")),
+                    destination_sub_path: LocalPath(format!("files/{index}")),
+                    sha256: Some(Sha256(String::new())),
+                    sha512: None,
+                    blake3: None,
+                    size,
+                })
+                .collect(),
+            resources,
+            ..test_utils::package("Test")
+        }
+    }
+
+    #[test]
+    fn reports_shortfalls() {
+        let package = package(
+            &[],
+            Some(Resources {
+                min_memory: Some(8 << 30),
+                min_cpu_cores: Some(4),
+                disk_space: Some(200 << 20),
+            }),
+        );
+
+        let plenty = Available {
+            disk_space: Some(100 << 30),
+            memory: Some(16 << 30),
+            cpu_cores: Some(8),
+        };
+        check([&package], &plenty).unwrap();
+        check([&package], &Available::default()).unwrap();
+
+        let scarce = Available {
+            disk_space: Some(100 << 20),
+            memory: Some(4 << 30),
+            cpu_cores: None,
+        };
+        let PreflightError(shortfalls) = check([&package], &scarce).unwrap_err();
+        assert_eq!(
+            shortfalls,
+            [
+                Shortfall::DiskSpace {
+                    required: 200 << 20,
+                    available: 100 << 20,
+                },
+                Shortfall::Memory {
+                    required: 8 << 30,
+                    available: 4 << 30,
+                },
+            ]
+        );
+        assert_eq!(
+            shortfalls[0].to_string(),
+            "not enough disk space: 200.0 MiB required, 100.0 MiB available"
+        );
+    }
+
+    #[test]
+    fn sums_disk_space_of_packages() {
+        let resources = |disk_space, min_memory| Resources {
+            disk_space: Some(disk_space),
+            min_memory: Some(min_memory),
+            min_cpu_cores: None,
+        };
+        let dependency = package(&[], Some(resources(150 << 20, 2 << 30)));
+        let dependent = package(&[], Some(resources(100 << 20, 1 << 30)));
+        let unknown = package(&[None], None);
+        let available = Available {
+            disk_space: Some(200 << 20),
+            memory: Some(2 << 30),
+            cpu_cores: None,
+        };
+        check([&dependency], &available).unwrap();
+        check([&dependent, &unknown], &available).unwrap();
+
+        let PreflightError(shortfalls) =
+            check([&dependency, &dependent, &unknown], &available).unwrap_err();
+        assert_eq!(
+            shortfalls,
+            [Shortfall::DiskSpace {
+                required: 250 << 20,
+                available: 200 << 20,
+            }]
+        );
+    }
+
+    #[test]
+    fn sums_file_sizes() {
+        assert_eq!(disk_space(&package(&[Some(10), Some(20)], None)), Some(30));
+        assert_eq!(disk_space(&package(&[Some(10), None], None)), None);
+        let declared = Resources {
+            disk_space: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(disk_space(&package(&[None], Some(declared))), Some(100));
+    }
+}
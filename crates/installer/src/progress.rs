@@ -0,0 +1,48 @@
+//! The installation progress events.
+
+use std::{path::PathBuf, time::Duration};
+
+/// An installation progress event.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Started the installation.
+    Started {
+        /// The number of files to download.
+        files: usize,
+        /// The total size of the files in bytes, if all of the sizes are known.
+        total_bytes: Option<u64>,
+    },
+    /// Started downloading a file; sent again for every retry or mirror
+    /// the file is loaded from, with the received bytes starting over.
+    FileStarted {
+        /// The local path to the file.
+        path: PathBuf,
+        /// The URL the file is loaded from.
+        url: String,
+        /// The expected size of the file in bytes, if known.
+        size: Option<u64>,
+    },
+    /// Received a part of a file.
+    FileProgress {
+        /// The local path to the file.
+        path: PathBuf,
+        /// The number of bytes received so far.
+        received: u64,
+        /// The expected size of the file in bytes, if known.
+        size: Option<u64>,
+    },
+    /// Downloaded a file and verified its hashes.
+    FileVerified {
+        /// The local path to the file.
+        path: PathBuf,
+        /// The size of the file in bytes.
+        size: u64,
+        /// The time the download took, including the retries.
+        duration: Duration,
+    },
+    /// Set the executable permissions of a file.
+    PermissionsSet {
+        /// The local path to the file.
+        path: PathBuf,
+    },
+}
@@ -0,0 +1,92 @@
+//! The download rate limiting.
+
+use std::{
+    num::NonZeroU64,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// A download rate limiter.
+///
+/// The clones share the same budget, so all of the downloads using them
+/// together stay within the rate.
+#[derive(Debug, Clone)]
+pub struct Limiter {
+    /// The allowed rate, in bytes per second.
+    bytes_per_second: NonZeroU64,
+    /// The shared budget.
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+/// The budget of a token bucket that holds up to a second worth of bytes.
+#[derive(Debug)]
+struct Bucket {
+    /// The number of bytes that can be transferred right away; negative when
+    /// the transferred bytes have yet to be waited for.
+    available: f64,
+    /// When the budget was last refilled.
+    refilled_at: Instant,
+}
+
+impl Limiter {
+    /// Create a new limiter with the given rate, in bytes per second.
+    pub fn new(bytes_per_second: NonZeroU64) -> Self {
+        Self {
+            bytes_per_second,
+            bucket: Arc::new(Mutex::new(Bucket {
+                available: bytes_per_second.get() as f64,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// The allowed rate, in bytes per second.
+    pub fn bytes_per_second(&self) -> NonZeroU64 {
+        self.bytes_per_second
+    }
+
+    /// Account the transferred bytes, waiting until the rate allows them.
+    pub async fn consume(&self, bytes: u64) {
+        let rate = self.bytes_per_second.get() as f64;
+
+        let delay = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+            bucket.available = (bucket.available + elapsed * rate).min(rate);
+            bucket.refilled_at = now;
+            bucket.available -= bytes as f64;
+            if bucket.available < 0.0 {
+                Duration::from_secs_f64(-bucket.available / rate)
+            } else {
+                Duration::ZERO
+            }
+        };
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn limits_shared_rate() {
+        let limiter = Limiter::new(NonZeroU64::new(1000).unwrap());
+        let other = limiter.clone();
+        let started_at = Instant::now();
+
+        // A second worth of bytes is available right away.
+        limiter.consume(1000).await;
+        assert_eq!(started_at.elapsed(), Duration::ZERO);
+
+        // The clones share the budget.
+        tokio::join!(limiter.consume(1000), other.consume(1000));
+        assert_eq!(started_at.elapsed().as_secs(), 2);
+    }
+}
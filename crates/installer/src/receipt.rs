@@ -0,0 +1,125 @@
+//! The install receipts.
+//!
+//! A receipt is written to the installation directory of every package, so
+//! the installed package can be told later, for instance to update it.
+
+use std::path::{Path, PathBuf};
+
+use humanode_distribution_schema::manifest::{Package, PackageId, Version};
+use serde::{Deserialize, Serialize};
+
+/// The name of the receipt file in the installation directory.
+pub const FILE_NAME: &str = ".humanode-distribution.json";
+
+/// The record of an installed package.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    /// The identifier of the package, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<PackageId>,
+    /// The version of the package, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+    /// The display name of the package at the time of the installation.
+    pub display_name: String,
+    /// The URL of the manifest the package was installed from.
+    pub manifest_url: String,
+}
+
+impl Receipt {
+    /// Describe the package installed from the given manifest.
+    pub fn new(package: &Package, manifest_url: String) -> Self {
+        Self {
+            id: package.id.clone(),
+            version: package.version.clone(),
+            display_name: package.display_name.clone(),
+            manifest_url,
+        }
+    }
+}
+
+/// An error that can happen when reading or writing a receipt.
+#[derive(Debug, thiserror::Error)]
+pub enum ReceiptError {
+    /// Failed to read or write the receipt file.
+    #[error("unable to access the receipt {path}: {error}")]
+    Io {
+        /// The path to the receipt.
+        path: PathBuf,
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+    /// The receipt is malformed.
+    #[error("invalid receipt {path}: {error}")]
+    Parse {
+        /// The path to the receipt.
+        path: PathBuf,
+        /// The underlying error.
+        #[source]
+        error: serde_json::Error,
+    },
+}
+
+/// Read the receipt of the package installed to the given directory, if any.
+pub async fn read(dir: &Path) -> Result<Option<Receipt>, ReceiptError> {
+    let path = dir.join(FILE_NAME);
+    let data = match tokio::fs::read(&path).await {
+        Ok(data) => data,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(ReceiptError::Io { path, error }),
+    };
+    serde_json::from_slice(&data)
+        .map(Some)
+        .map_err(|error| ReceiptError::Parse { path, error })
+}
+
+/// Write the receipt to the given directory, replacing the existing one.
+pub async fn write(dir: &Path, receipt: &Receipt) -> Result<(), ReceiptError> {
+    let path = dir.join(FILE_NAME);
+    let data = serde_json::to_vec_pretty(receipt).map_err(|error| ReceiptError::Parse {
+        path: path.clone(),
+        error,
+    })?;
+    tokio::fs::write(&path, data)
+        .await
+        .map_err(|error| ReceiptError::Io { path, error })
+}
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::test_utils;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read(dir.path()).await.unwrap().is_none());
+
+        let package = Package {
+            id: Some(PackageId("humanode-peer".into())),
+            version: Some(Version("0.1.0".into())),
+            ..test_utils::package("Humanode Peer")
+        };
+        let receipt = Receipt::new(&package, "https://example.com/manifest.json".into());
+        write(dir.path(), &receipt).await.unwrap();
+
+        let read = read(dir.path()).await.unwrap().unwrap();
+        assert_eq!(read.id, Some(PackageId("humanode-peer".into())));
+        assert_eq!(read.version.unwrap().0, "0.1.0");
+        assert_eq!(read.display_name, "Humanode Peer");
+        assert_eq!(read.manifest_url, "https://example.com/manifest.json");
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_receipts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(FILE_NAME), "not json").unwrap();
+        assert!(matches!(
+            read(dir.path()).await,
+            Err(ReceiptError::Parse { .. })
+        ));
+    }
+}
@@ -0,0 +1,44 @@
+//! The human-readable sizes.
+
+/// The byte size, displayed in the binary units with a decimal place, like
+/// `1.5 MiB`; the sizes under a KiB are displayed in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bytes(pub u64);
+
+impl std::fmt::Display for Bytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displays_binary_units() {
+        let cases = [
+            (0, "0 B"),
+            (1023, "1023 B"),
+            (1024, "1.0 KiB"),
+            (1536, "1.5 KiB"),
+            (200 << 20, "200.0 MiB"),
+            (3 << 30, "3.0 GiB"),
+            (2048 << 40, "2048.0 TiB"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(Bytes(value).to_string(), expected, "{value}");
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! The CPU features and the microarchitecture levels.
+//!
+//! The features are named the way `is_x86_feature_detected!` and
+//! `is_aarch64_feature_detected!` name them, and the x86-64
+//! microarchitecture levels, like `x86-64-v3`, stand for all the features
+//! they imply.
+
+use std::collections::BTreeSet;
+
+/// The x86-64 microarchitecture levels, each with the features it adds to
+/// the previous one.
+pub const X86_64_LEVELS: &[(&str, &[&str])] = &[
+    (
+        "x86-64-v2",
+        &["cmpxchg16b", "popcnt", "sse3", "sse4.1", "sse4.2", "ssse3"],
+    ),
+    (
+        "x86-64-v3",
+        &[
+            "avx", "avx2", "bmi1", "bmi2", "f16c", "fma", "lzcnt", "movbe", "xsave",
+        ],
+    ),
+    (
+        "x86-64-v4",
+        &["avx512f", "avx512bw", "avx512cd", "avx512dq", "avx512vl"],
+    ),
+];
+
+/// Expand the microarchitecture levels among the features into all
+/// the features they imply, including the lower levels.
+pub fn expand<'a>(features: impl IntoIterator<Item = &'a str>) -> BTreeSet<String> {
+    let mut expanded = BTreeSet::new();
+    for feature in features {
+        let feature = feature.to_ascii_lowercase();
+        let level = X86_64_LEVELS
+            .iter()
+            .position(|(level, _)| *level == feature);
+        match level {
+            Some(index) => {
+                for (level, level_features) in &X86_64_LEVELS[..=index] {
+                    expanded.insert((*level).to_owned());
+                    expanded.extend(level_features.iter().map(|&feature| feature.to_owned()));
+                }
+            }
+            None => {
+                expanded.insert(feature);
+            }
+        }
+    }
+    expanded
+}
+
+/// The microarchitecture levels the features satisfy.
+pub fn levels(features: &BTreeSet<String>) -> Vec<&'static str> {
+    X86_64_LEVELS
+        .iter()
+        .take_while(|(_, level_features)| {
+            level_features
+                .iter()
+                .all(|&feature| features.contains(feature))
+        })
+        .map(|(level, _)| *level)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_levels() {
+        let expanded = expand(["x86-64-v3", "AES"]);
+        for feature in ["x86-64-v2", "x86-64-v3", "sse4.2", "avx2", "aes"] {
+            assert!(expanded.contains(feature), "{feature}");
+        }
+        assert!(!expanded.contains("x86-64-v4"));
+        assert!(!expanded.contains("avx512f"));
+    }
+
+    #[test]
+    fn detects_levels() {
+        let cases: [(&[&str], &[&str]); 4] = [
+            (&[], &[]),
+            (&["x86-64-v2"], &["x86-64-v2"]),
+            (&["x86-64-v4"], &["x86-64-v2", "x86-64-v3", "x86-64-v4"]),
+            // The higher level features without the lower level ones do
+            // not count.
+            (
+                &["avx512f", "avx512bw", "avx512cd", "avx512dq", "avx512vl"],
+                &[],
+            ),
+        ];
+        for (features, expected) in cases {
+            let features = expand(features.iter().copied());
+            assert_eq!(levels(&features), expected, "{features:?}");
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! The canonical platform and architecture identifiers.
+//!
+//! Systems and manifests spell the same platform or architecture in
+//! different ways, for instance macOS reports `arm64` while Linux reports
+//! `aarch64` for the same CPU architecture. The values are mapped to
+//! the canonical identifiers via the alias tables before being compared.
+
+use std::borrow::Cow;
+
+pub mod cpu;
+
+/// The platform aliases, mapped to the canonical platform identifiers.
+///
+/// The canonical identifiers are the ones `uname -s` reports.
+const PLATFORM_ALIASES: &[(&str, &str)] = &[
+    ("darwin", "Darwin"),
+    ("macos", "Darwin"),
+    ("osx", "Darwin"),
+    ("linux", "Linux"),
+    ("windows", "Windows"),
+    ("win32", "Windows"),
+];
+
+/// The architecture aliases, mapped to the canonical architecture
+/// identifiers.
+const ARCH_ALIASES: &[(&str, &str)] = &[
+    ("x86_64", "x86_64"),
+    ("amd64", "x86_64"),
+    ("x64", "x86_64"),
+    ("arm64", "arm64"),
+    ("aarch64", "arm64"),
+    ("i386", "x86"),
+    ("i686", "x86"),
+    ("x86", "x86"),
+];
+
+/// Look the value up in the alias table, ignoring the case.
+///
+/// Unknown values are returned as is.
+fn canonical<'a>(aliases: &[(&str, &'static str)], value: &'a str) -> Cow<'a, str> {
+    aliases
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(value))
+        .map_or(Cow::Borrowed(value), |(_, canonical)| {
+            Cow::Borrowed(*canonical)
+        })
+}
+
+/// Get the canonical identifier of the platform.
+pub fn platform(value: &str) -> Cow<'_, str> {
+    canonical(PLATFORM_ALIASES, value)
+}
+
+/// Get the canonical identifier of the architecture.
+pub fn arch(value: &str) -> Cow<'_, str> {
+    canonical(ARCH_ALIASES, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platforms() {
+        let cases = [
+            ("Darwin", "Darwin"),
+            ("darwin", "Darwin"),
+            ("macOS", "Darwin"),
+            ("Linux", "Linux"),
+            ("linux", "Linux"),
+            ("Windows", "Windows"),
+            ("win32", "Windows"),
+            ("FreeBSD", "FreeBSD"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(platform(value), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn arches() {
+        let cases = [
+            ("x86_64", "x86_64"),
+            ("amd64", "x86_64"),
+            ("AMD64", "x86_64"),
+            ("x64", "x86_64"),
+            ("arm64", "arm64"),
+            ("aarch64", "arm64"),
+            ("i686", "x86"),
+            ("riscv64", "riscv64"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(arch(value), expected, "{value}");
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! The cache of the fetched meta documents.
+//!
+//! Every successfully fetched repo and manifest document is stored in the cache
+//! along with the time it was fetched at, so that the resolution can still
+//! be performed when the network is unavailable.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+/// An error that can happen when working with the cache.
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    /// The requested URL is not in the cache.
+    #[error("no cached copy of {0} is available")]
+    Missing(String),
+    /// The document is not a valid UTF-8 text and can't be cached.
+    #[error("document is not a valid UTF-8 text: {0}")]
+    NotUtf8(#[source] std::str::Utf8Error),
+    /// Reading or writing the cache entry failed.
+    #[error("cache I/O error at {1}: {0}")]
+    Io(#[source] std::io::Error, PathBuf),
+    /// The cache entry is malformed.
+    #[error("cache entry {1} is malformed: {0}")]
+    Serde(#[source] serde_yaml_bw::Error, PathBuf),
+}
+
+/// A single cache entry, as persisted on disk.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    /// The URL the document was fetched from.
+    url: String,
+    /// The time the document was fetched at, in seconds since the Unix epoch.
+    fetched_at: u64,
+    /// The raw document.
+    data: String,
+}
+
+/// A document loaded from the cache.
+#[derive(Debug)]
+pub struct Cached {
+    /// The raw document.
+    pub data: String,
+    /// The time the document was fetched at.
+    pub fetched_at: SystemTime,
+}
+
+/// The on-disk cache of the fetched documents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cache {
+    /// The directory to keep the cache entries at.
+    pub dir: PathBuf,
+}
+
+impl Cache {
+    /// Create a new cache at the given directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The path to the entry for the given URL.
+    fn entry_path(&self, url: &str) -> PathBuf {
+        let key = hex::encode(sha2::Sha256::digest(url.as_bytes()));
+        self.dir.join(format!("{key}.yml"))
+    }
+
+    /// Store the freshly fetched document in the cache.
+    pub async fn store(&self, url: &str, data: &[u8]) -> Result<(), CacheError> {
+        let data = std::str::from_utf8(data).map_err(CacheError::NotUtf8)?;
+        let fetched_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let entry = Entry {
+            url: url.to_owned(),
+            fetched_at,
+            data: data.to_owned(),
+        };
+
+        let path = self.entry_path(url);
+        let serialized =
+            serde_yaml_bw::to_string(&entry).map_err(|err| CacheError::Serde(err, path.clone()))?;
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|err| CacheError::Io(err, self.dir.clone()))?;
+
+        // Write to a temporary file first to never leave a partial entry behind.
+        let tmp_path = path.with_extension("yml.tmp");
+        tokio::fs::write(&tmp_path, serialized)
+            .await
+            .map_err(|err| CacheError::Io(err, tmp_path.clone()))?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .map_err(|err| CacheError::Io(err, path))?;
+
+        Ok(())
+    }
+
+    /// Load the last fetched document for the given URL from the cache.
+    pub async fn load(&self, url: &str) -> Result<Cached, CacheError> {
+        let path = self.entry_path(url);
+        let raw = match tokio::fs::read(&path).await {
+            Ok(val) => val,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(CacheError::Missing(url.to_owned()))
+            }
+            Err(err) => return Err(CacheError::Io(err, path)),
+        };
+
+        let entry: Entry =
+            serde_yaml_bw::from_slice(&raw).map_err(|err| CacheError::Serde(err, path))?;
+        if entry.url != url {
+            return Err(CacheError::Missing(url.to_owned()));
+        }
+
+        Ok(Cached {
+            data: entry.data,
+            fetched_at: SystemTime::UNIX_EPOCH + Duration::from_secs(entry.fetched_at),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stores_and_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().join("cache"));
+        let url = "https://example.com/manifest.yml";
+
+        let before = SystemTime::now() - Duration::from_secs(1);
+        cache.store(url, b"first").await.unwrap();
+        cache.store(url, b"second").await.unwrap();
+
+        let cached = cache.load(url).await.unwrap();
+        assert_eq!(cached.data, "second");
+        assert!(cached.fetched_at >= before && cached.fetched_at <= SystemTime::now());
+    }
+
+    #[tokio::test]
+    async fn writes_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        let url = "https://example.com/manifest.yml";
+
+        cache.store(url, b"data").await.unwrap();
+
+        // Only the complete entry is left behind, with no temporary file.
+        let files: Vec<PathBuf> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files, [cache.entry_path(url)]);
+    }
+
+    #[tokio::test]
+    async fn reports_misses() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        let url = "https://example.com/manifest.yml";
+        let other_url = "https://example.com/other.yml";
+
+        let error = cache.load(url).await.unwrap_err();
+        assert!(matches!(error, CacheError::Missing(ref missing) if missing == url));
+
+        // An entry that belongs to another URL is not a hit.
+        cache.store(other_url, b"data").await.unwrap();
+        std::fs::rename(cache.entry_path(other_url), cache.entry_path(url)).unwrap();
+        let error = cache.load(url).await.unwrap_err();
+        assert!(matches!(error, CacheError::Missing(ref missing) if missing == url));
+    }
+
+    #[tokio::test]
+    async fn rejects_non_utf8_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        let error = cache
+            .store("https://example.com/manifest.yml", &[0xff, 0xfe])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, CacheError::NotUtf8(_)));
+    }
+}
@@ -0,0 +1,492 @@
+//! The package dependencies.
+//!
+//! The dependencies are resolved greedily: the newest available version
+//! that satisfies the requirements known at the time is picked for each
+//! package, and the requirements found later must be satisfied by it too.
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    path::{Component, Path},
+};
+
+use humanode_distribution_schema::manifest::{Dependency, Package, PackageId};
+
+use crate::version::{self, Requirement, RequirementError};
+
+/// A package to install, along with the place to install it to.
+#[derive(Debug)]
+pub struct Step<'a, T> {
+    /// The package.
+    pub package: &'a T,
+    /// The sub path of the installation directory to install the package
+    /// to; not set for the requested package itself.
+    pub sub_path: Option<String>,
+}
+
+/// An error that can occur when resolving the dependencies.
+#[derive(Debug, thiserror::Error)]
+pub enum DependencyError {
+    /// The version requirement is not valid.
+    #[error("{dependent} has an invalid version requirement on {id}: {error}")]
+    InvalidRequirement {
+        /// The display name of the package that declares the dependency.
+        dependent: String,
+        /// The identifier of the dependency.
+        id: PackageId,
+        /// The underlying error.
+        #[source]
+        error: RequirementError,
+    },
+    /// No package with the identifier is available.
+    #[error("{dependent} depends on {id}, which is not available")]
+    NotFound {
+        /// The display name of the package that declares the dependency.
+        dependent: String,
+        /// The identifier of the dependency.
+        id: PackageId,
+    },
+    /// None of the available versions satisfies all the requirements.
+    #[error("no available version of {id} satisfies {}", .requirements.join(", "))]
+    Unsatisfiable {
+        /// The identifier of the dependency.
+        id: PackageId,
+        /// The requirements along with the packages that declare them.
+        requirements: Vec<String>,
+    },
+    /// The packages depend on each other.
+    #[error("dependency cycle: {}", .ids.iter().map(|id| id.0.as_str()).collect::<Vec<_>>().join(" -> "))]
+    Cycle {
+        /// The identifiers of the packages in the cycle, with the first one
+        /// repeated at the end.
+        ids: Vec<PackageId>,
+    },
+    /// The sub path points outside of the installation directory, or to
+    /// the installation directory itself.
+    #[error(
+        "{dependent} installs {id} to {sub_path:?}, which is not a subdirectory of the installation directory"
+    )]
+    InvalidSubPath {
+        /// The display name of the package that declares the dependency.
+        dependent: String,
+        /// The identifier of the dependency.
+        id: PackageId,
+        /// The sub path as declared.
+        sub_path: String,
+    },
+    /// The dependents install the same package to different places.
+    #[error("{id} is to be installed to both {first:?} and {second:?}")]
+    SubPathConflict {
+        /// The identifier of the dependency.
+        id: PackageId,
+        /// The sub path the package is installed to first.
+        first: String,
+        /// The other sub path.
+        second: String,
+    },
+}
+
+/// Compute the packages to install along with the given one, picking them
+/// from the available ones.
+///
+/// The steps are in the installation order, so the dependencies go before
+/// their dependents, and the requested package goes last.
+/// Among the packages with the same version the earlier ones in the list
+/// are preferred, so the list is expected to be ranked already.
+pub fn plan<'a, T: AsRef<Package>>(
+    root: &'a T,
+    available: &'a [T],
+) -> Result<Vec<Step<'a, T>>, DependencyError> {
+    let mut planner = Planner {
+        available,
+        chosen: HashMap::new(),
+        stack: Vec::new(),
+        steps: Vec::new(),
+    };
+    planner.visit(root)?;
+    planner.steps.push(Step {
+        package: root,
+        sub_path: None,
+    });
+    Ok(planner.steps)
+}
+
+/// A version requirement along with its origin.
+struct Constraint {
+    /// The display name of the package that declares the requirement.
+    dependent: String,
+    /// The requirement as declared.
+    declared: String,
+    /// The parsed requirement.
+    requirement: Requirement,
+}
+
+impl Constraint {
+    /// Parse the version requirement of the dependency, if any.
+    fn new(dependent: &Package, dependency: &Dependency) -> Result<Option<Self>, DependencyError> {
+        let Some(ref version) = dependency.version else {
+            return Ok(None);
+        };
+        let requirement =
+            version
+                .0
+                .parse()
+                .map_err(|error| DependencyError::InvalidRequirement {
+                    dependent: dependent.display_name.clone(),
+                    id: dependency.id.clone(),
+                    error,
+                })?;
+        Ok(Some(Self {
+            dependent: dependent.display_name.clone(),
+            declared: version.0.clone(),
+            requirement,
+        }))
+    }
+}
+
+impl std::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} (required by {})", self.declared, self.dependent)
+    }
+}
+
+/// A dependency picked for the installation.
+struct Chosen<'a, T> {
+    /// The package.
+    package: &'a T,
+    /// The sub path to install the package to.
+    sub_path: String,
+    /// The requirements the package must satisfy.
+    constraints: Vec<Constraint>,
+}
+
+/// The state of the dependency resolution.
+struct Planner<'a, T> {
+    /// The packages to pick the dependencies from.
+    available: &'a [T],
+    /// The dependencies picked so far.
+    chosen: HashMap<PackageId, Chosen<'a, T>>,
+    /// The identifiers of the packages being visited, outermost first.
+    stack: Vec<PackageId>,
+    /// The steps planned so far.
+    steps: Vec<Step<'a, T>>,
+}
+
+impl<'a, T: AsRef<Package>> Planner<'a, T> {
+    /// Pick the dependencies of the package, planning them before it.
+    fn visit(&mut self, package: &'a T) -> Result<(), DependencyError> {
+        let package = package.as_ref();
+        if let Some(ref id) = package.id {
+            self.stack.push(id.clone());
+        }
+
+        for dependency in &package.dependencies {
+            let id = &dependency.id;
+            if let Some(position) = self.stack.iter().position(|visited| visited == id) {
+                let mut ids = self.stack[position..].to_vec();
+                ids.push(id.clone());
+                return Err(DependencyError::Cycle { ids });
+            }
+
+            let constraint = Constraint::new(package, dependency)?;
+            let sub_path = dependency
+                .destination_sub_path
+                .as_ref()
+                .map_or_else(|| id.0.clone(), |path| path.0.clone());
+            if !is_contained(&sub_path) {
+                return Err(DependencyError::InvalidSubPath {
+                    dependent: package.display_name.clone(),
+                    id: id.clone(),
+                    sub_path,
+                });
+            }
+
+            if let Some(chosen) = self.chosen.get_mut(id) {
+                if chosen.sub_path != sub_path {
+                    return Err(DependencyError::SubPathConflict {
+                        id: id.clone(),
+                        first: chosen.sub_path.clone(),
+                        second: sub_path,
+                    });
+                }
+                if let Some(constraint) = constraint {
+                    let fits = satisfies(chosen.package.as_ref(), &constraint);
+                    chosen.constraints.push(constraint);
+                    if !fits {
+                        return Err(DependencyError::Unsatisfiable {
+                            id: id.clone(),
+                            requirements: describe(&chosen.constraints),
+                        });
+                    }
+                }
+                continue;
+            }
+
+            let constraints: Vec<Constraint> = constraint.into_iter().collect();
+            let mut found = false;
+            let mut best: Option<&'a T> = None;
+            for candidate in self.available {
+                let candidate_package = candidate.as_ref();
+                if candidate_package.id.as_ref() != Some(id) {
+                    continue;
+                }
+                found = true;
+                let fits = constraints
+                    .iter()
+                    .all(|constraint| satisfies(candidate_package, constraint));
+                let newer = best.is_none_or(|best| {
+                    compare_versions(candidate_package, best.as_ref()) == Ordering::Greater
+                });
+                if fits && newer {
+                    best = Some(candidate);
+                }
+            }
+            let Some(best) = best else {
+                return Err(if found {
+                    DependencyError::Unsatisfiable {
+                        id: id.clone(),
+                        requirements: describe(&constraints),
+                    }
+                } else {
+                    DependencyError::NotFound {
+                        dependent: package.display_name.clone(),
+                        id: id.clone(),
+                    }
+                });
+            };
+
+            self.chosen.insert(
+                id.clone(),
+                Chosen {
+                    package: best,
+                    sub_path: sub_path.clone(),
+                    constraints,
+                },
+            );
+            self.visit(best)?;
+            self.steps.push(Step {
+                package: best,
+                sub_path: Some(sub_path),
+            });
+        }
+
+        if package.id.is_some() {
+            self.stack.pop();
+        }
+        Ok(())
+    }
+}
+
+/// Check whether the package version satisfies the requirement; a package
+/// without a version satisfies none.
+fn satisfies(package: &Package, constraint: &Constraint) -> bool {
+    package
+        .version
+        .as_ref()
+        .is_some_and(|version| constraint.requirement.matches(&version.0))
+}
+
+/// Compare the package versions; a package without a version is older
+/// than any other.
+fn compare_versions(a: &Package, b: &Package) -> Ordering {
+    match (&a.version, &b.version) {
+        (Some(a), Some(b)) => version::compare(&a.0, &b.0),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+/// Check whether the sub path points to a subdirectory of the directory it
+/// is joined to, that is it is relative, does not go up, and is not empty
+/// or the directory itself.
+fn is_contained(sub_path: &str) -> bool {
+    let mut components = Path::new(sub_path).components();
+    components
+        .clone()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        && components.any(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Describe the requirements for the error.
+fn describe(constraints: &[Constraint]) -> Vec<String> {
+    constraints.iter().map(ToString::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::{
+        manifest::{Manifest, Version},
+        test_utils,
+    };
+    use humanode_distribution_transport::{memory::Memory, retry};
+
+    use super::*;
+    use crate::resolve::{self, Contextualized};
+
+    /// A package with the given identifier, version and dependencies,
+    /// in YAML.
+    fn package(id: &str, version: &str, dependencies: &str) -> Contextualized<Package> {
+        Contextualized {
+            manifest_url: "https://example.com/manifest.yml".to_owned(),
+            mirror_urls: Vec::new(),
+            cached_at: None,
+            value: Package {
+                id: Some(PackageId(id.into())),
+                version: Some(Version(version.into())),
+                dependencies: serde_yaml_bw::from_str(dependencies).unwrap(),
+                ..test_utils::package(&format!("Package {id} {version}"))
+            },
+        }
+    }
+
+    /// Describe the plan as the display names along with the sub paths.
+    fn describe_plan(steps: &[Step<'_, Contextualized<Package>>]) -> Vec<String> {
+        steps
+            .iter()
+            .map(|step| match step.sub_path {
+                Some(ref sub_path) => format!("{} at {sub_path}", step.package.value.display_name),
+                None => step.package.value.display_name.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn orders_dependencies() {
+        let root = package(
+            "peer",
+            "1.0",
+            "[{id: tunnel, version: '>=0.2'}, {id: ngrok, destinationSubPath: tools/ngrok}]",
+        );
+        let available = [
+            package("tunnel", "0.1", "[]"),
+            package("tunnel", "0.3", "[{id: ngrok}]"),
+            package("tunnel", "0.2", "[]"),
+            package("ngrok", "3.0", "[]"),
+        ];
+
+        let error = plan(&root, &available).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "ngrok is to be installed to both \"ngrok\" and \"tools/ngrok\""
+        );
+
+        let root = package(
+            "peer",
+            "1.0",
+            "[{id: tunnel, version: '>=0.2'}, {id: ngrok}]",
+        );
+        let steps = plan(&root, &available).unwrap();
+        assert_eq!(
+            describe_plan(&steps),
+            [
+                "Package ngrok 3.0 at ngrok",
+                "Package tunnel 0.3 at tunnel",
+                "Package peer 1.0",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn picks_resolved_versions() {
+        let manifest = Manifest {
+            schema_version: None,
+            packages: vec![
+                package("tunnel", "0.1", "[]").value,
+                package("tunnel", "0.3", "[]").value,
+                package("peer", "1.0", "[{id: tunnel, version: '>=0.2'}]").value,
+            ],
+            mirrors: Vec::new(),
+        };
+        let url = "https://example.com/manifest.yml";
+        let transport = Memory::default().with(url, serde_yaml_bw::to_string(&manifest).unwrap());
+
+        let mut issues = Vec::new();
+        let packages = resolve::resolve(
+            &transport,
+            resolve::Params {
+                manifest_urls: vec![url.to_owned()],
+                repo_urls: Vec::new(),
+                cache: None,
+                mode: resolve::Mode::Online,
+                strict: true,
+                retry_policy: retry::Policy::no_retries(),
+            },
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert!(issues.is_empty());
+
+        let root = packages
+            .iter()
+            .find(|package| package.value.display_name == "Package peer 1.0")
+            .unwrap();
+        let steps = plan(root, &packages).unwrap();
+        assert_eq!(
+            describe_plan(&steps),
+            ["Package tunnel 0.3 at tunnel", "Package peer 1.0"]
+        );
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let available = [
+            package("tunnel", "0.3", "[]"),
+            package("ngrok", "3.0", "[{id: tunnel, version: '<0.3'}]"),
+        ];
+        let root = package(
+            "peer",
+            "1.0",
+            "[{id: tunnel, version: '>=0.2'}, {id: ngrok}]",
+        );
+        let error = plan(&root, &available).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "no available version of tunnel satisfies \
+             \">=0.2\" (required by Package peer 1.0), \
+             \"<0.3\" (required by Package ngrok 3.0)"
+        );
+
+        let available = [
+            package("tunnel", "0.3", "[{id: ngrok}]"),
+            package("ngrok", "3.0", "[{id: tunnel}]"),
+        ];
+        let root = package("peer", "1.0", "[{id: tunnel}]");
+        let error = plan(&root, &available).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "dependency cycle: tunnel -> ngrok -> tunnel"
+        );
+
+        let root = package("peer", "1.0", "[{id: bench}]");
+        let error = plan(&root, &available).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Package peer 1.0 depends on bench, which is not available"
+        );
+
+        for sub_path in [
+            "/usr/bin",
+            "../tunnel",
+            "tools/../../tunnel",
+            "",
+            ".",
+            "./.",
+        ] {
+            let root = package(
+                "peer",
+                "1.0",
+                &format!("[{{id: tunnel, destinationSubPath: {sub_path:?}}}]"),
+            );
+            let error = plan(&root, &available).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "Package peer 1.0 installs tunnel to {sub_path:?}, \
+                     which is not a subdirectory of the installation directory"
+                )
+            );
+        }
+    }
+}
@@ -1,20 +1,292 @@
 //! The filtering logic.
 
-use humanode_distribution_schema::manifest::Package;
+use std::{cmp::Reverse, collections::BTreeSet};
+
+use humanode_distribution_schema::manifest::{LibcKind, Package, Requirements};
+
+use crate::version;
+
+pub mod expr;
 
 /// Filter params.
+///
+/// The platforms and architectures are compared by their canonical
+/// identifiers, so the aliases match each other.
+///
+/// The package requirements are only checked against the system details
+/// that are known; the unknown ones do not reject the packages. The CPU
+/// features are the exception, as a build for the missing ones crashes:
+/// only the listed features count as available.
 pub struct Params {
     /// Platform.
     pub platform: String,
     /// Architecture.
     pub arch: String,
+    /// The architectures the system can run via binary translation, in
+    /// the order of preference; the packages for these are the fallbacks
+    /// for the lack of the native ones.
+    pub translated_arches: Vec<String>,
+    /// The OS version, if known.
+    pub os_version: Option<String>,
+    /// The Linux distribution identifier, like `ubuntu`, if known; not set
+    /// on the other platforms.
+    pub distribution: Option<String>,
+    /// The kernel release, if known.
+    pub kernel_release: Option<String>,
+    /// The C library, if known.
+    pub libc: Option<Libc>,
+    /// The features of the CPU, along with the microarchitecture levels
+    /// they satisfy.
+    pub cpu_features: Vec<String>,
+}
+
+/// The C library of the system.
+pub struct Libc {
+    /// The C library flavour.
+    pub kind: LibcKind,
+    /// The C library version, if known.
+    pub version: Option<String>,
+}
+
+/// The way a matching package runs on the system.
+///
+/// Ordered from the most to the least preferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Support {
+    /// The package runs natively.
+    Native,
+    /// The package runs via binary translation.
+    Emulated,
+}
+
+/// How well a matching package suits the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fit {
+    /// The way the package runs on the system.
+    pub support: Support,
+    /// The number of the CPU features the package is built for, including
+    /// the implied ones; the more there are, the more optimized the build is.
+    pub cpu_features: usize,
+}
+
+impl Fit {
+    /// The key to rank the packages by, the best fitting one going first:
+    /// the native packages before the emulated ones, and the more optimized
+    /// builds before the baseline ones.
+    pub fn rank(&self) -> (Support, Reverse<usize>) {
+        (self.support, Reverse(self.cpu_features))
+    }
+}
+
+/// A reason for the filter to reject a package.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Rejection {
+    /// The package is for another platform.
+    #[error("platform {actual} does not match {expected}")]
+    Platform {
+        /// The platform the filter requires.
+        expected: String,
+        /// The platform of the package.
+        actual: String,
+    },
+    /// The package is for another architecture.
+    #[error("arch {actual} does not match {expected}")]
+    Arch {
+        /// The architecture the filter requires.
+        expected: String,
+        /// The architectures of the package, separated by slashes.
+        actual: String,
+    },
+    /// The OS is older than the package requires.
+    #[error("OS version {actual} is older than the required {required}")]
+    OsVersion {
+        /// The minimum OS version the package requires.
+        required: String,
+        /// The OS version of the system.
+        actual: String,
+    },
+    /// The kernel is older than the package requires.
+    #[error("kernel release {actual} is older than the required {required}")]
+    KernelVersion {
+        /// The minimum kernel release the package requires.
+        required: String,
+        /// The kernel release of the system.
+        actual: String,
+    },
+    /// The package is built against another C library.
+    #[error("libc {actual} does not match the required {required}")]
+    Libc {
+        /// The C library the package requires.
+        required: LibcKind,
+        /// The C library of the system.
+        actual: LibcKind,
+    },
+    /// The C library is older than the package requires.
+    #[error("libc version {actual} is older than the required {required}")]
+    LibcVersion {
+        /// The minimum C library version the package requires.
+        required: String,
+        /// The C library version of the system.
+        actual: String,
+    },
+    /// The CPU lacks the features the package is built for.
+    #[error("CPU features {} are not available", missing.join(", "))]
+    CpuFeatures {
+        /// The missing features.
+        missing: Vec<String>,
+    },
 }
 
 impl Params {
     /// Check if the package matches the filter.
     pub fn matches(&self, item: impl AsRef<Package>) -> bool {
+        self.check(item).is_ok()
+    }
+
+    /// All the reasons the filter rejects the package for; empty if
+    /// the package matches.
+    pub fn rejections(&self, item: impl AsRef<Package>) -> Vec<Rejection> {
+        self.check(item).err().unwrap_or_default()
+    }
+
+    /// Check the package against the filter, returning how well it suits
+    /// the system if it matches, or all the reasons it is rejected for.
+    pub fn check(&self, item: impl AsRef<Package>) -> Result<Fit, Vec<Rejection>> {
         let package = item.as_ref();
-        package.platform.0 == self.platform && package.arch.0 == self.arch
+        let mut rejections = Vec::new();
+        if humanode_distribution_platform::platform(&package.platform.0)
+            != humanode_distribution_platform::platform(&self.platform)
+        {
+            rejections.push(Rejection::Platform {
+                expected: self.platform.clone(),
+                actual: package.platform.0.clone(),
+            });
+        }
+
+        let support = self.arch_support(package);
+        if support.is_none() {
+            let arches: Vec<&str> = package_arches(package).map(|arch| arch.as_str()).collect();
+            rejections.push(Rejection::Arch {
+                expected: self.arch.clone(),
+                actual: arches.join("/"),
+            });
+        }
+
+        let mut cpu_features = 0;
+        if let Some(requirements) = &package.requirements {
+            self.check_requirements(requirements, &mut rejections);
+            cpu_features = self.check_cpu_features(requirements, support, &mut rejections);
+        }
+
+        match support {
+            Some(support) if rejections.is_empty() => Ok(Fit {
+                support,
+                cpu_features,
+            }),
+            _ => Err(rejections),
+        }
+    }
+
+    /// Check the package requirements against the known system details.
+    fn check_requirements(&self, requirements: &Requirements, rejections: &mut Vec<Rejection>) {
+        let Requirements {
+            min_os_version,
+            distribution,
+            min_kernel_version,
+            libc,
+            cpu_features: _,
+        } = requirements;
+
+        // The versions of different distributions are not comparable, and
+        // the version without a distribution is the macOS product version.
+        let same_distribution = match (distribution, &self.distribution) {
+            (Some(required), Some(actual)) => required.eq_ignore_ascii_case(actual),
+            (required, actual) => required.is_none() && actual.is_none(),
+        };
+        if let (Some(required), Some(actual), true) =
+            (min_os_version, &self.os_version, same_distribution)
+        {
+            if !version::is_at_least(actual, &required.0) {
+                rejections.push(Rejection::OsVersion {
+                    required: required.0.clone(),
+                    actual: actual.clone(),
+                });
+            }
+        }
+
+        if let (Some(required), Some(actual)) = (min_kernel_version, &self.kernel_release) {
+            if !version::is_at_least(actual, &required.0) {
+                rejections.push(Rejection::KernelVersion {
+                    required: required.0.clone(),
+                    actual: actual.clone(),
+                });
+            }
+        }
+
+        if let (Some(required), Some(actual)) = (libc, &self.libc) {
+            if required.kind != actual.kind {
+                rejections.push(Rejection::Libc {
+                    required: required.kind,
+                    actual: actual.kind,
+                });
+            } else if let (Some(min_version), Some(version)) =
+                (&required.min_version, &actual.version)
+            {
+                if !version::is_at_least(version, &min_version.0) {
+                    rejections.push(Rejection::LibcVersion {
+                        required: min_version.0.clone(),
+                        actual: version.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Check the CPU features the package is built for, returning their
+    /// number, including the implied ones.
+    ///
+    /// The CPU features are only known for the native architecture, so
+    /// the emulated packages must not require any.
+    fn check_cpu_features(
+        &self,
+        requirements: &Requirements,
+        support: Option<Support>,
+        rejections: &mut Vec<Rejection>,
+    ) -> usize {
+        let required = humanode_distribution_platform::cpu::expand(
+            requirements.cpu_features.iter().map(String::as_str),
+        );
+        let available: BTreeSet<String> = if support == Some(Support::Emulated) {
+            BTreeSet::new()
+        } else {
+            humanode_distribution_platform::cpu::expand(
+                self.cpu_features.iter().map(String::as_str),
+            )
+        };
+
+        let missing: Vec<String> = required.difference(&available).cloned().collect();
+        if !missing.is_empty() {
+            rejections.push(Rejection::CpuFeatures { missing });
+        }
+        required.len()
+    }
+
+    /// Determine the way the package runs on the system architecture-wise,
+    /// if it does.
+    fn arch_support(&self, package: &Package) -> Option<Support> {
+        let arches: Vec<_> = package_arches(package)
+            .map(|arch| humanode_distribution_platform::arch(arch))
+            .collect();
+
+        if arches.contains(&humanode_distribution_platform::arch(&self.arch)) {
+            return Some(Support::Native);
+        }
+
+        let translated = self
+            .translated_arches
+            .iter()
+            .any(|arch| arches.contains(&humanode_distribution_platform::arch(arch)));
+        translated.then_some(Support::Emulated)
     }
 
     /// Filter the input packages with the filtering params.
@@ -25,3 +297,284 @@ impl Params {
         items.into_iter().filter(|item| self.matches(item))
     }
 }
+
+/// All the architectures the package declares.
+fn package_arches(package: &Package) -> impl Iterator<Item = &String> {
+    std::iter::once(&package.arch.0).chain(package.additional_arches.iter().map(|arch| &arch.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::{
+        manifest::{Arch, Platform},
+        test_utils,
+    };
+
+    use super::*;
+    use crate::resolve::Contextualized;
+
+    /// The filter params for the given platform and arch, with the rest of
+    /// the system details unknown.
+    fn params(platform: &str, arch: &str) -> Params {
+        Params {
+            platform: platform.to_owned(),
+            arch: arch.to_owned(),
+            translated_arches: Vec::new(),
+            os_version: None,
+            distribution: None,
+            kernel_release: None,
+            libc: None,
+            cpu_features: Vec::new(),
+        }
+    }
+
+    /// A package for the given platform and arch.
+    fn package(platform: &str, arch: &str) -> Contextualized<Package> {
+        package_with(platform, arch, &[])
+    }
+
+    /// A package for the given platform and arches.
+    fn package_with(
+        platform: &str,
+        arch: &str,
+        additional_arches: &[&str],
+    ) -> Contextualized<Package> {
+        contextualized(Package {
+            platform: Platform(platform.into()),
+            arch: Arch(arch.into()),
+            additional_arches: additional_arches
+                .iter()
+                .map(|&arch| Arch(arch.into()))
+                .collect(),
+            ..test_utils::package("Test")
+        })
+    }
+
+    /// A Linux package with the given requirements, in YAML.
+    fn package_requiring(requirements: &str) -> Contextualized<Package> {
+        contextualized(Package {
+            requirements: Some(serde_yaml_bw::from_str(requirements).unwrap()),
+            ..test_utils::package("Test")
+        })
+    }
+
+    /// Put the package into a manifest context.
+    fn contextualized(value: Package) -> Contextualized<Package> {
+        Contextualized {
+            manifest_url: "https://example.com/manifest.yml".to_owned(),
+            mirror_urls: Vec::new(),
+            cached_at: None,
+            value,
+        }
+    }
+
+    #[test]
+    fn matches_aliases() {
+        let cases = [
+            ("Linux", "aarch64", "Linux", "arm64", true),
+            ("Darwin", "arm64", "Darwin", "aarch64", true),
+            ("Linux", "amd64", "Linux", "x86_64", true),
+            ("linux", "x86_64", "Linux", "x86_64", true),
+            ("Darwin", "x86_64", "Darwin", "arm64", false),
+            ("Windows", "x86_64", "Linux", "x86_64", false),
+        ];
+        for (host_platform, host_arch, platform, arch, expected) in cases {
+            let params = params(host_platform, host_arch);
+            assert_eq!(
+                params.matches(package(platform, arch)),
+                expected,
+                "{host_platform} {host_arch} vs {platform} {arch}"
+            );
+        }
+    }
+
+    #[test]
+    fn explains_rejections() {
+        let params = params("Linux", "x86_64");
+
+        assert!(params.rejections(package("Linux", "x86_64")).is_empty());
+        assert_eq!(
+            params.rejections(package("Darwin", "arm64")),
+            [
+                Rejection::Platform {
+                    expected: "Linux".to_owned(),
+                    actual: "Darwin".to_owned(),
+                },
+                Rejection::Arch {
+                    expected: "x86_64".to_owned(),
+                    actual: "arm64".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ranks_emulated_fallbacks() {
+        let params = Params {
+            translated_arches: vec!["x86_64".to_owned()],
+            ..params("Darwin", "arm64")
+        };
+
+        let cases = [
+            (package("Darwin", "arm64"), Ok(Support::Native)),
+            (package("Darwin", "aarch64"), Ok(Support::Native)),
+            (package("Darwin", "x86_64"), Ok(Support::Emulated)),
+            (package("Darwin", "amd64"), Ok(Support::Emulated)),
+            (
+                package_with("Darwin", "x86_64", &["arm64"]),
+                Ok(Support::Native),
+            ),
+            (
+                package("Linux", "x86_64"),
+                Err(vec![Rejection::Platform {
+                    expected: "Darwin".to_owned(),
+                    actual: "Linux".to_owned(),
+                }]),
+            ),
+            (
+                package_with("Darwin", "riscv64", &["ppc64"]),
+                Err(vec![Rejection::Arch {
+                    expected: "arm64".to_owned(),
+                    actual: "riscv64/ppc64".to_owned(),
+                }]),
+            ),
+        ];
+        for (package, expected) in cases {
+            assert_eq!(
+                params.check(&package).map(|fit| fit.support),
+                expected,
+                "{:?}",
+                package.value
+            );
+        }
+
+        let native_only = Params {
+            translated_arches: Vec::new(),
+            ..params
+        };
+        assert!(!native_only.matches(package("Darwin", "x86_64")));
+        assert!(Support::Native < Support::Emulated);
+    }
+
+    #[test]
+    fn enforces_requirements() {
+        let system = Params {
+            os_version: Some("22.04".to_owned()),
+            distribution: Some("ubuntu".to_owned()),
+            kernel_release: Some("5.15.0-91-generic".to_owned()),
+            libc: Some(Libc {
+                kind: LibcKind::Glibc,
+                version: Some("2.35".to_owned()),
+            }),
+            ..params("Linux", "x86_64")
+        };
+
+        let cases = [
+            ("{}", vec![]),
+            (
+                "{distribution: ubuntu, minOsVersion: '20.04', minKernelVersion: '5.4', libc: {kind: glibc, minVersion: '2.31'}}",
+                vec![],
+            ),
+            // The versions of the other distributions and of macOS do not
+            // apply.
+            ("{distribution: debian, minOsVersion: '99'}", vec![]),
+            ("{minOsVersion: '99'}", vec![]),
+            (
+                "{distribution: Ubuntu, minOsVersion: '24.04'}",
+                vec![Rejection::OsVersion {
+                    required: "24.04".to_owned(),
+                    actual: "22.04".to_owned(),
+                }],
+            ),
+            (
+                "{minKernelVersion: '6.1'}",
+                vec![Rejection::KernelVersion {
+                    required: "6.1".to_owned(),
+                    actual: "5.15.0-91-generic".to_owned(),
+                }],
+            ),
+            (
+                "{libc: {kind: glibc, minVersion: '2.39'}}",
+                vec![Rejection::LibcVersion {
+                    required: "2.39".to_owned(),
+                    actual: "2.35".to_owned(),
+                }],
+            ),
+            (
+                "{libc: {kind: musl}}",
+                vec![Rejection::Libc {
+                    required: LibcKind::Musl,
+                    actual: LibcKind::Glibc,
+                }],
+            ),
+        ];
+        for (requirements, expected) in cases {
+            let package = package_requiring(requirements);
+            assert_eq!(system.rejections(&package), expected, "{requirements}");
+        }
+
+        let macos = Params {
+            os_version: Some("13.6".to_owned()),
+            ..params("Darwin", "arm64")
+        };
+        let mut package = package_requiring("{minOsVersion: '14.0'}");
+        package.value.platform = Platform("Darwin".into());
+        package.value.arch = Arch("arm64".into());
+        assert_eq!(
+            macos.rejections(&package),
+            [Rejection::OsVersion {
+                required: "14.0".to_owned(),
+                actual: "13.6".to_owned(),
+            }]
+        );
+
+        // The unknown system details do not reject anything.
+        let unknown = params("Linux", "x86_64");
+        let package = package_requiring(
+            "{minOsVersion: '99', minKernelVersion: '99', libc: {kind: musl, minVersion: '99'}}",
+        );
+        assert!(unknown.matches(&package));
+    }
+
+    #[test]
+    fn ranks_optimized_builds() {
+        let system = Params {
+            cpu_features: vec!["x86-64-v3".to_owned(), "aes".to_owned()],
+            ..params("Linux", "x86_64")
+        };
+
+        let baseline = system.check(package_requiring("{}")).unwrap();
+        let v2 = system
+            .check(package_requiring("{cpuFeatures: [x86-64-v2]}"))
+            .unwrap();
+        let v3 = system
+            .check(package_requiring("{cpuFeatures: [x86-64-v3, aes]}"))
+            .unwrap();
+        assert!(v3.rank() < v2.rank());
+        assert!(v2.rank() < baseline.rank());
+
+        assert_eq!(
+            system.rejections(package_requiring("{cpuFeatures: [x86-64-v3, sha]}")),
+            [Rejection::CpuFeatures {
+                missing: vec!["sha".to_owned()],
+            }]
+        );
+        let rejections = system.rejections(package_requiring("{cpuFeatures: [x86-64-v4]}"));
+        assert!(
+            matches!(
+                &rejections[..],
+                [Rejection::CpuFeatures { missing }] if missing.contains(&"avx512f".to_owned())
+            ),
+            "{rejections:?}"
+        );
+
+        // The emulated packages can't rely on the native CPU features.
+        let emulated = Params {
+            translated_arches: vec!["x86_64".to_owned()],
+            cpu_features: vec!["aes".to_owned()],
+            ..params("Linux", "arm64")
+        };
+        assert!(emulated.matches(package_requiring("{}")));
+        assert!(!emulated.matches(package_requiring("{cpuFeatures: [aes]}")));
+    }
+}
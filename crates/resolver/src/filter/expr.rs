@@ -0,0 +1,721 @@
+//! The filter expressions.
+//!
+//! An expression selects the packages by their fields, for instance:
+//!
+//! ```text
+//! name ~ "Humanode*" && !(description contains "test" || arch == arm64)
+//! ```
+//!
+//! The fields are `id`, `name`, `version`, `channel`, `description`,
+//! `manifest`, `platform` and `arch`; the `id`, the `version` and the
+//! `channel` of a package without one are empty.
+//! The operators are `==` and `!=` for the exact comparison, `~` and `!~`
+//! for the glob matching, with `*` matching any sequence of characters and
+//! `?` any single character, and `contains` for the substring search.
+//! The `version` is also compared with `<`, `<=`, `>` and `>=`, and all of
+//! its comparisons but the glob and the substring ones are by the version
+//! order, so `1.0` equals `1.0.0`; a package without a version matches none
+//! of those but `!=`.
+//! The `platform` and the `arch` are compared by their canonical
+//! identifiers with `==` and `!=`, so `arch == amd64` matches `x86_64`.
+//! The comparisons are combined with `&&`, `||`, `!` and the parentheses.
+//! The values are either double-quoted strings, with `\"` and `\\`
+//! escapes, or bare words.
+
+use std::{iter::Peekable, str::CharIndices};
+
+use humanode_distribution_schema::manifest::Package;
+
+use crate::{resolve::Contextualized, version};
+
+/// A filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// Both of the expressions match.
+    And(Box<Expr>, Box<Expr>),
+    /// Either of the expressions matches.
+    Or(Box<Expr>, Box<Expr>),
+    /// The expression does not match.
+    Not(Box<Expr>),
+    /// The field compares to the value.
+    Compare {
+        /// The field to compare.
+        field: Field,
+        /// The comparison.
+        op: Op,
+        /// The value to compare to.
+        value: String,
+    },
+}
+
+/// A package field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// The identifier.
+    Id,
+    /// The display name.
+    Name,
+    /// The version.
+    Version,
+    /// The release channel.
+    Channel,
+    /// The description.
+    Description,
+    /// The URL of the manifest the package comes from.
+    Manifest,
+    /// The platform.
+    Platform,
+    /// The architecture.
+    Arch,
+}
+
+/// A comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// The field equals the value.
+    Eq,
+    /// The field does not equal the value.
+    Ne,
+    /// The field matches the glob.
+    Glob,
+    /// The field does not match the glob.
+    NotGlob,
+    /// The field contains the value.
+    Contains,
+    /// The version is older than the value.
+    Lt,
+    /// The version is older than or the same as the value.
+    Le,
+    /// The version is newer than the value.
+    Gt,
+    /// The version is newer than or the same as the value.
+    Ge,
+}
+
+/// An error that can happen when parsing an expression.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    /// The expression ended prematurely.
+    #[error("unexpected end of the expression, expected {expected}")]
+    UnexpectedEnd {
+        /// What was expected.
+        expected: &'static str,
+    },
+    /// An unexpected token was found.
+    #[error("unexpected {found:?} at {position}, expected {expected}")]
+    Unexpected {
+        /// The byte position of the token.
+        position: usize,
+        /// The token found.
+        found: String,
+        /// What was expected.
+        expected: &'static str,
+    },
+    /// The field is not known.
+    #[error("unknown field {name:?} at {position}")]
+    UnknownField {
+        /// The byte position of the field.
+        position: usize,
+        /// The field name.
+        name: String,
+    },
+    /// The string is not terminated.
+    #[error("unterminated string at {position}")]
+    UnterminatedString {
+        /// The byte position of the string.
+        position: usize,
+    },
+}
+
+impl std::str::FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?.into_iter().peekable(),
+        };
+        let expr = parser.or()?;
+        match parser.tokens.next() {
+            None => Ok(expr),
+            Some((position, token)) => Err(ParseError::Unexpected {
+                position,
+                found: token.to_string(),
+                expected: "&&, || or the end of the expression",
+            }),
+        }
+    }
+}
+
+impl Expr {
+    /// Check if the package matches the expression.
+    pub fn matches(&self, package: &Contextualized<Package>) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(package) && b.matches(package),
+            Self::Or(a, b) => a.matches(package) || b.matches(package),
+            Self::Not(expr) => !expr.matches(package),
+            Self::Compare { field, op, value } => {
+                let actual = field.get(package);
+                match op {
+                    Op::Glob => glob(value, actual),
+                    Op::NotGlob => !glob(value, actual),
+                    Op::Contains => actual.contains(value.as_str()),
+                    Op::Eq => field.equals(actual, value),
+                    Op::Ne => !field.equals(actual, value),
+                    Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+                        // Only the version is ordered, see the parser.
+                        if *field != Field::Version || actual.is_empty() {
+                            return false;
+                        }
+                        let ordering = version::compare(actual, value);
+                        match op {
+                            Op::Lt => ordering.is_lt(),
+                            Op::Le => ordering.is_le(),
+                            Op::Gt => ordering.is_gt(),
+                            _ => ordering.is_ge(),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Field {
+    /// Get the field value of the package.
+    fn get(self, package: &Contextualized<Package>) -> &str {
+        match self {
+            Self::Id => package.value.id.as_ref().map_or("", |id| &id.0),
+            Self::Name => &package.value.display_name,
+            Self::Version => package
+                .value
+                .version
+                .as_ref()
+                .map_or("", |version| &version.0),
+            Self::Channel => package
+                .value
+                .channel
+                .as_ref()
+                .map_or("", |channel| &channel.0),
+            Self::Description => &package.value.description,
+            Self::Manifest => &package.manifest_url,
+            Self::Platform => &package.value.platform.0,
+            Self::Arch => &package.value.arch.0,
+        }
+    }
+
+    /// Check whether the field value equals the given one.
+    fn equals(self, actual: &str, value: &str) -> bool {
+        match self {
+            Self::Version => !actual.is_empty() && version::compare(actual, value).is_eq(),
+            Self::Platform => {
+                humanode_distribution_platform::platform(actual)
+                    == humanode_distribution_platform::platform(value)
+            }
+            Self::Arch => {
+                humanode_distribution_platform::arch(actual)
+                    == humanode_distribution_platform::arch(value)
+            }
+            _ => actual == value,
+        }
+    }
+
+    /// Look the field up by name.
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "id" => Self::Id,
+            "name" => Self::Name,
+            "version" => Self::Version,
+            "channel" => Self::Channel,
+            "description" => Self::Description,
+            "manifest" => Self::Manifest,
+            "platform" => Self::Platform,
+            "arch" => Self::Arch,
+            _ => return None,
+        })
+    }
+}
+
+/// Check if the value matches the glob pattern.
+fn glob(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    // The positions to backtrack to on a mismatch: right after the last
+    // star in the pattern, and the value position it is matched up to.
+    let mut backtrack = None;
+    let (mut p, mut v) = (0, 0);
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, v));
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_v)) => {
+                    p = star_p;
+                    v = star_v + 1;
+                    backtrack = Some((star_p, star_v + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A token of the expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A bare word.
+    Word(String),
+    /// A quoted string.
+    Str(String),
+    /// `&&`.
+    And,
+    /// `||`.
+    Or,
+    /// `!`.
+    Not,
+    /// `(`.
+    Open,
+    /// `)`.
+    Close,
+    /// A comparison operator.
+    Op(Op),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Word(word) => f.write_str(word),
+            Self::Str(value) => write!(f, "{value:?}"),
+            Self::And => f.write_str("&&"),
+            Self::Or => f.write_str("||"),
+            Self::Not => f.write_str("!"),
+            Self::Open => f.write_str("("),
+            Self::Close => f.write_str(")"),
+            Self::Op(Op::Eq) => f.write_str("=="),
+            Self::Op(Op::Ne) => f.write_str("!="),
+            Self::Op(Op::Glob) => f.write_str("~"),
+            Self::Op(Op::NotGlob) => f.write_str("!~"),
+            Self::Op(Op::Contains) => f.write_str("contains"),
+            Self::Op(Op::Lt) => f.write_str("<"),
+            Self::Op(Op::Le) => f.write_str("<="),
+            Self::Op(Op::Gt) => f.write_str(">"),
+            Self::Op(Op::Ge) => f.write_str(">="),
+        }
+    }
+}
+
+/// Whether the character can be a part of a bare word.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-.:/*?@+".contains(c)
+}
+
+/// Split the expression into the tokens along with their byte positions.
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '"' => {
+                chars.next();
+                Token::Str(string(&mut chars, position)?)
+            }
+            '(' | ')' | '~' => {
+                chars.next();
+                match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Op(Op::Glob),
+                }
+            }
+            '<' | '>' => {
+                chars.next();
+                let or_equal = chars.next_if(|&(_, c)| c == '=').is_some();
+                Token::Op(match (c, or_equal) {
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    _ => Op::Ge,
+                })
+            }
+            '&' | '|' | '=' | '!' => {
+                chars.next();
+                let next = chars.peek().map(|&(_, c)| c);
+                let token = match (c, next) {
+                    ('&', Some('&')) => Token::And,
+                    ('|', Some('|')) => Token::Or,
+                    ('=', Some('=')) => Token::Op(Op::Eq),
+                    ('!', Some('=')) => Token::Op(Op::Ne),
+                    ('!', Some('~')) => Token::Op(Op::NotGlob),
+                    ('!', _) => {
+                        tokens.push((position, Token::Not));
+                        continue;
+                    }
+                    _ => {
+                        return Err(ParseError::Unexpected {
+                            position,
+                            found: c.to_string(),
+                            expected: "an operator",
+                        })
+                    }
+                };
+                chars.next();
+                token
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if !is_word_char(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                if word == "contains" {
+                    Token::Op(Op::Contains)
+                } else {
+                    Token::Word(word)
+                }
+            }
+            c => {
+                return Err(ParseError::Unexpected {
+                    position,
+                    found: c.to_string(),
+                    expected: "a token",
+                })
+            }
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+/// Read the rest of a quoted string.
+fn string(chars: &mut Peekable<CharIndices<'_>>, position: usize) -> Result<String, ParseError> {
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some((_, '"')) => return Ok(value),
+            Some((_, '\\')) => match chars.next() {
+                Some((_, c)) => value.push(c),
+                None => break,
+            },
+            Some((_, c)) => value.push(c),
+            None => break,
+        }
+    }
+    Err(ParseError::UnterminatedString { position })
+}
+
+/// The recursive descent parser.
+struct Parser {
+    /// The tokens to parse.
+    tokens: Peekable<std::vec::IntoIter<(usize, Token)>>,
+}
+
+impl Parser {
+    /// Take the next token, failing at the end.
+    fn next(&mut self, expected: &'static str) -> Result<(usize, Token), ParseError> {
+        self.tokens
+            .next()
+            .ok_or(ParseError::UnexpectedEnd { expected })
+    }
+
+    /// Take the next token if it is the given one.
+    fn eat(&mut self, token: &Token) -> bool {
+        self.tokens.next_if(|(_, next)| next == token).is_some()
+    }
+
+    /// Parse the disjunction.
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    /// Parse the conjunction.
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    /// Parse the negation, the parenthesized expression or the comparison.
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        const EXPECTED: &str = "a field, ! or (";
+        match self.next(EXPECTED)? {
+            (_, Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            (_, Token::Open) => {
+                let expr = self.or()?;
+                match self.next(")")? {
+                    (_, Token::Close) => Ok(expr),
+                    (position, token) => Err(ParseError::Unexpected {
+                        position,
+                        found: token.to_string(),
+                        expected: ")",
+                    }),
+                }
+            }
+            (position, Token::Word(name)) => {
+                let field =
+                    Field::from_name(&name).ok_or(ParseError::UnknownField { position, name })?;
+                self.comparison(field)
+            }
+            (position, token) => Err(ParseError::Unexpected {
+                position,
+                found: token.to_string(),
+                expected: EXPECTED,
+            }),
+        }
+    }
+
+    /// Parse the rest of the comparison after the field.
+    fn comparison(&mut self, field: Field) -> Result<Expr, ParseError> {
+        let op = match self.next("an operator")? {
+            (position, Token::Op(op @ (Op::Lt | Op::Le | Op::Gt | Op::Ge)))
+                if field != Field::Version =>
+            {
+                return Err(ParseError::Unexpected {
+                    position,
+                    found: Token::Op(op).to_string(),
+                    expected: "==, !=, ~, !~ or contains, as only the version is ordered",
+                })
+            }
+            (_, Token::Op(op)) => op,
+            (position, token) => {
+                return Err(ParseError::Unexpected {
+                    position,
+                    found: token.to_string(),
+                    expected: "an operator",
+                })
+            }
+        };
+        let value = match self.next("a value")? {
+            (_, Token::Word(value) | Token::Str(value)) => value,
+            (position, token) => {
+                return Err(ParseError::Unexpected {
+                    position,
+                    found: token.to_string(),
+                    expected: "a value",
+                })
+            }
+        };
+        Ok(Expr::Compare { field, op, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::{
+        manifest::{Arch, Channel, Version},
+        test_utils,
+    };
+
+    use super::*;
+
+    /// A package with the given name, description, arch and version.
+    fn package(
+        name: &str,
+        description: &str,
+        arch: &str,
+        version: Option<&str>,
+    ) -> Contextualized<Package> {
+        Contextualized {
+            manifest_url: "https://example.com/beta/manifest.yml".to_owned(),
+            mirror_urls: Vec::new(),
+            cached_at: None,
+            value: Package {
+                arch: Arch(arch.into()),
+                description: description.into(),
+                version: version.map(|version| Version(version.into())),
+                ..test_utils::package(name)
+            },
+        }
+    }
+
+    #[test]
+    fn parses() {
+        let compare = |field, op, value: &str| {
+            Box::new(Expr::Compare {
+                field,
+                op,
+                value: value.to_owned(),
+            })
+        };
+        assert_eq!(
+            r#"name ~ "Humanode*" && !(arch == arm64 || description contains "a \"b\"")"#
+                .parse::<Expr>()
+                .unwrap(),
+            Expr::And(
+                compare(Field::Name, Op::Glob, "Humanode*"),
+                Box::new(Expr::Not(Box::new(Expr::Or(
+                    compare(Field::Arch, Op::Eq, "arm64"),
+                    compare(Field::Description, Op::Contains, "a \"b\""),
+                )))),
+            )
+        );
+        // The conjunction binds tighter than the disjunction.
+        assert_eq!(
+            "version>=1.2 && version < 2".parse::<Expr>().unwrap(),
+            Expr::And(
+                compare(Field::Version, Op::Ge, "1.2"),
+                compare(Field::Version, Op::Lt, "2"),
+            )
+        );
+        assert_eq!(
+            "arch == a || arch == b && arch != c"
+                .parse::<Expr>()
+                .unwrap(),
+            Expr::Or(
+                compare(Field::Arch, Op::Eq, "a"),
+                Box::new(Expr::And(
+                    compare(Field::Arch, Op::Eq, "b"),
+                    compare(Field::Arch, Op::Ne, "c"),
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        let cases = [
+            (
+                "size == 1",
+                ParseError::UnknownField {
+                    position: 0,
+                    name: "size".to_owned(),
+                },
+            ),
+            (
+                "name ==",
+                ParseError::UnexpectedEnd {
+                    expected: "a value",
+                },
+            ),
+            (
+                "name == a b",
+                ParseError::Unexpected {
+                    position: 10,
+                    found: "b".to_owned(),
+                    expected: "&&, || or the end of the expression",
+                },
+            ),
+            ("(name == a", ParseError::UnexpectedEnd { expected: ")" }),
+            (
+                "name == \"a",
+                ParseError::UnterminatedString { position: 8 },
+            ),
+            (
+                "name < a",
+                ParseError::Unexpected {
+                    position: 5,
+                    found: "<".to_owned(),
+                    expected: "==, !=, ~, !~ or contains, as only the version is ordered",
+                },
+            ),
+            (
+                "name = a",
+                ParseError::Unexpected {
+                    position: 5,
+                    found: "=".to_owned(),
+                    expected: "an operator",
+                },
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<Expr>(), Err(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn matches_packages() {
+        let peer = package("Humanode Peer", "The peer node.", "x86_64", Some("1.2.0"));
+        let cases = [
+            (r#"name == "Humanode Peer""#, true),
+            (r#"name != "Humanode Peer""#, false),
+            (r#"name ~ "Humanode*""#, true),
+            (r#"name ~ "*Peer""#, true),
+            (r#"name ~ "H?manode *""#, true),
+            (r#"name ~ "*Node*""#, false),
+            (r#"name !~ "*Node*""#, true),
+            ("description contains peer", true),
+            ("description contains Peer", false),
+            ("manifest ~ */beta/*", true),
+            ("platform == Linux && arch == x86_64", true),
+            ("arch == arm64 || arch == x86_64", true),
+            ("!(arch == x86_64)", false),
+            ("arch == amd64 && platform == linux", true),
+            ("arch != AMD64", false),
+            ("arch ~ amd*", false),
+            ("version == 1.2", true),
+            ("version != 1.2", false),
+            ("version >= 1.2 && version < 1.10", true),
+            ("version > 1.2.0 || version <= 1.1", false),
+            ("version ~ 1.2.*", true),
+        ];
+        for (input, expected) in cases {
+            let expr: Expr = input.parse().unwrap();
+            assert_eq!(expr.matches(&peer), expected, "{input}");
+        }
+
+        let unversioned = package("Humanode Peer", "The peer node.", "x86_64", None);
+        let cases = [
+            ("version == \"\"", false),
+            ("version >= 0", false),
+            ("version < 1", false),
+            ("version != 1", true),
+        ];
+        for (input, expected) in cases {
+            let expr: Expr = input.parse().unwrap();
+            assert_eq!(expr.matches(&unversioned), expected, "{input}");
+        }
+
+        let mut beta = package("Humanode Peer", "The peer node.", "x86_64", Some("1.3.0"));
+        beta.value.channel = Some(Channel("beta".into()));
+        let cases = [
+            ("channel == beta", true),
+            ("channel != beta", false),
+            ("channel == beta && version > 1.2", true),
+            ("channel ~ b*", true),
+            ("channel == stable", false),
+        ];
+        for (input, expected) in cases {
+            let expr: Expr = input.parse().unwrap();
+            assert_eq!(expr.matches(&beta), expected, "{input}");
+        }
+        let expr: Expr = "channel == beta".parse().unwrap();
+        assert!(!expr.matches(&peer));
+    }
+
+    #[test]
+    fn globs() {
+        let cases = [
+            ("*", "", true),
+            ("*", "abc", true),
+            ("a*c", "abbbc", true),
+            ("a*c", "abbbd", false),
+            ("a*b*c", "aXbYbZc", true),
+            ("?", "", false),
+            ("a?c", "abc", true),
+            ("abc", "abcd", false),
+            ("**a", "bba", true),
+        ];
+        for (pattern, value, expected) in cases {
+            assert_eq!(glob(pattern, value), expected, "{pattern} {value}");
+        }
+    }
+}
@@ -1,43 +1,59 @@
 //! The HTTP utils.
 
+use humanode_distribution_transport::{
+    retry::{self, Classify, Verdict},
+    Transport,
+};
+
 /// An error that can happen when the loading stuff.
 #[derive(Debug, thiserror::Error)]
 pub enum LoadError {
-    /// A failure from `reqwest`.
-    #[error("reqwest error: {0}")]
-    Reqwest(#[source] reqwest::Error),
-    /// The server returned a bad status code.
-    #[error("server error: {0}")]
-    Server(reqwest::StatusCode),
+    /// A failure from the transport.
+    #[error(transparent)]
+    Transport(humanode_distribution_transport::Error),
     /// Deserialization error.
     #[error("serde error: {0}")]
     Serde(#[source] serde_yaml_bw::Error),
 }
 
+impl Classify for LoadError {
+    fn classify(&self) -> Verdict {
+        match self {
+            Self::Transport(error) => error.classify(),
+            Self::Serde(_) => Verdict::Fatal,
+        }
+    }
+}
+
 /// Load a meta URL and parse it as a YAML document.
-pub async fn load_meta<T>(client: &reqwest::Client, url: &str) -> Result<T, LoadError>
+pub async fn load_meta<T>(
+    transport: &impl Transport,
+    retry_policy: &retry::Policy,
+    url: &str,
+) -> Result<T, LoadError>
 where
     T: for<'de> serde::Deserialize<'de>,
 {
-    let req = client
-        .get(url)
-        .header(
-            reqwest::header::ACCEPT,
-            "application/json,application/x-yaml,text/yaml",
-        )
-        .build()
-        .map_err(LoadError::Reqwest)?;
-
-    let res = client.execute(req).await.map_err(LoadError::Reqwest)?;
-
-    let status = res.status();
-    if !status.is_success() {
-        return Err(LoadError::Server(status));
-    }
-
-    let bytes = res.bytes().await.map_err(LoadError::Reqwest)?;
+    let bytes = load_meta_bytes(transport, retry_policy, url).await?;
+    parse_meta(&bytes)
+}
 
-    let repo: T = serde_yaml_bw::from_slice(&bytes).map_err(LoadError::Serde)?;
+/// Load the raw contents of a meta URL without parsing them, retrying
+/// the transient failures.
+pub async fn load_meta_bytes(
+    transport: &impl Transport,
+    retry_policy: &retry::Policy,
+    url: &str,
+) -> Result<bytes::Bytes, LoadError> {
+    retry::retry(retry_policy, || transport.fetch(url))
+        .await
+        .map_err(LoadError::Transport)
+}
 
-    Ok(repo)
+/// Parse the raw contents of a meta document as YAML.
+pub fn parse_meta<T>(bytes: &[u8]) -> Result<T, LoadError>
+where
+    T: for<'de> serde::Deserialize<'de>,
+{
+    serde_yaml_bw::from_slice(bytes).map_err(LoadError::Serde)
 }
@@ -1,5 +1,9 @@
 //! The resolver logic.
 
+pub mod cache;
+pub mod dependencies;
 pub mod filter;
 pub mod http;
+pub mod progress;
 pub mod resolve;
+pub mod version;
@@ -0,0 +1,50 @@
+//! The resolution progress events.
+
+use std::time::{Duration, SystemTime};
+
+use crate::resolve::Document;
+
+/// A resolution progress event.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Started fetching a document.
+    FetchStarted {
+        /// The document URL.
+        url: String,
+        /// The kind of the document.
+        document: Document,
+    },
+    /// Fetched a document.
+    FetchFinished {
+        /// The document URL.
+        url: String,
+        /// The kind of the document.
+        document: Document,
+        /// The size of the document in bytes.
+        bytes: usize,
+        /// The time the fetching took, including the retries.
+        duration: Duration,
+    },
+    /// Failed to fetch a document; the failure itself is reported as
+    /// an issue.
+    FetchFailed {
+        /// The document URL.
+        url: String,
+        /// The kind of the document.
+        document: Document,
+        /// The time spent before giving up, including the retries.
+        duration: Duration,
+    },
+    /// Loaded the packages from a manifest.
+    ManifestResolved {
+        /// The manifest URL.
+        url: String,
+        /// The number of packages the manifest lists.
+        found: usize,
+        /// The number of the listed packages rejected by the filter.
+        filtered_out: usize,
+        /// The time the manifest was fetched at, if it was served from
+        /// the cache rather than fetched just now.
+        cached_at: Option<SystemTime>,
+    },
+}
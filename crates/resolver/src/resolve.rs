@@ -1,35 +1,159 @@
 //! Resolver.
 
-use std::collections::HashSet;
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    time::{Instant, SystemTime},
+};
 
 use futures::{pin_mut, Sink, SinkExt};
 use humanode_distribution_schema::{
-    manifest::{Manifest, Package},
+    manifest::{Manifest, Package, PackageId},
     repo::Repo,
 };
+use humanode_distribution_transport::{file, retry, Transport};
 use serde::{Deserialize, Serialize};
 
-use crate::http::load_meta;
+use crate::{
+    cache::{Cache, CacheError},
+    http::{load_meta_bytes, parse_meta, LoadError},
+    progress::Event,
+};
 
 /// An issue that occurred during resolution.
+///
+/// Displayed as the URL along with the error; the document kind and
+/// the include chain are only available through the fields.
 #[derive(Debug)]
 pub struct ResolutionError {
     /// The URL that was attempted.
     pub url: String,
-    /// The error description.
-    pub error: String,
+    /// The kind of the document that was attempted.
+    pub document: Document,
+    /// The URLs of the documents that led to this one, outermost first;
+    /// empty if the document was given directly.
+    pub include_chain: Vec<String>,
+    /// What went wrong.
+    pub kind: ErrorKind,
+}
+
+impl std::fmt::Display for ResolutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.url, self.kind)
+    }
+}
+
+impl std::error::Error for ResolutionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+/// The kind of a meta document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Document {
+    /// A repo, listing the manifests.
+    Repo,
+    /// A manifest, listing the packages.
+    Manifest,
 }
 
-impl ResolutionError {
-    /// Construct a new resolution issue.
-    pub fn from_display(url: String, err: impl std::fmt::Display) -> Self {
-        Self {
-            url,
-            error: err.to_string(),
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Repo => "repo",
+            Self::Manifest => "manifest",
+        })
+    }
+}
+
+/// The way loading a document failed.
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorKind {
+    /// The server responded with a non-success status.
+    #[error("server error: {0}")]
+    Status(reqwest::StatusCode),
+    /// The document could not be transferred.
+    #[error(transparent)]
+    Transport(humanode_distribution_transport::Error),
+    /// The document is not valid.
+    #[error("serde error: {error}")]
+    Parse {
+        /// The line the error is at, starting from one, if known.
+        line: Option<usize>,
+        /// The column the error is at, starting from one, if known.
+        column: Option<usize>,
+        /// The underlying error.
+        #[source]
+        error: serde_yaml_bw::Error,
+    },
+    /// Working with the cache failed.
+    #[error(transparent)]
+    Cache(CacheError),
+    /// The remote document refers to a local file.
+    #[error("remote documents can not refer to the local {0}")]
+    LocalUrl(String),
+    /// The document signature does not verify.
+    #[error("signature error: {0}")]
+    Signature(String),
+    /// The document is written in a newer schema version than supported.
+    #[error(
+        "unsupported schema version {0}, only up to {supported} is supported",
+        supported = humanode_distribution_schema::SCHEMA_VERSION
+    )]
+    UnsupportedVersion(u32),
+    /// The offline mode is used without a cache.
+    #[error("offline mode requires a cache, but none is configured")]
+    NoCache,
+    /// The package identifier is already used by another package of
+    /// the same version for the same platform and architecture.
+    #[error(
+        "package id {id}{} for {platform} {arch} is already declared by {manifest_url}",
+        .version.as_ref().map(|version| format!(" version {version}")).unwrap_or_default()
+    )]
+    DuplicateId {
+        /// The package identifier.
+        id: PackageId,
+        /// The package version, if any.
+        version: Option<String>,
+        /// The canonical platform of the packages.
+        platform: String,
+        /// The canonical architecture of the packages.
+        arch: String,
+        /// The URL of the manifest that declared the identifier first.
+        manifest_url: String,
+    },
+}
+
+impl From<LoadError> for ErrorKind {
+    fn from(error: LoadError) -> Self {
+        match error {
+            LoadError::Transport(humanode_distribution_transport::Error::Server {
+                status, ..
+            }) => Self::Status(status),
+            LoadError::Transport(error) => Self::Transport(error),
+            LoadError::Serde(error) => {
+                let location = error.location();
+                Self::Parse {
+                    line: location.as_ref().map(|location| location.line()),
+                    column: location.as_ref().map(|location| location.column()),
+                    error,
+                }
+            }
         }
     }
 }
 
+/// The way the documents are obtained.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mode {
+    /// Fetch the documents from the network, and fall back to the cache if
+    /// the fetch fails.
+    #[default]
+    Online,
+    /// Do not touch the network, and serve the documents from the cache only.
+    Offline,
+}
+
 /// The resolver params.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Params {
@@ -37,78 +161,400 @@ pub struct Params {
     pub manifest_urls: Vec<String>,
     /// The repo URLs.
     pub repo_urls: Vec<String>,
+    /// The cache to store the fetched documents at; no caching is done if
+    /// not set.
+    pub cache: Option<Cache>,
+    /// The way to obtain the documents.
+    pub mode: Mode,
+    /// Fail the resolution on the first repo or manifest that can't be
+    /// loaded, instead of reporting it and carrying on with the rest.
+    pub strict: bool,
+    /// The retry policy for the transient network failures.
+    #[serde(skip)]
+    pub retry_policy: retry::Policy,
 }
 
 /// The context-enhanced value.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Contextualized<T> {
     /// The manifest URL this value came from.
     pub manifest_url: String,
+    /// The mirror URLs declared by the manifest this value came from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirror_urls: Vec<String>,
+    /// The time the manifest was fetched at, if it was served from the cache
+    /// instead of being fetched just now; the value is stale if this is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_at: Option<SystemTime>,
     /// The value that is contextualized.
     pub value: T,
 }
 
+/// A loaded document.
+struct Loaded<T> {
+    /// The document.
+    value: T,
+    /// The time the document was fetched at, if it came from the cache.
+    cached_at: Option<SystemTime>,
+}
+
 /// Resolve the packages.
+///
+/// The package identifiers must be unique per version, platform and
+/// architecture across the resolved packages; the manifests are processed
+/// in the URL order, and the later packages reusing an identifier are
+/// reported and skipped, in the strict mode too.
+///
+/// In the strict mode the first document that can't be loaded is returned
+/// as an error; otherwise it is reported to the issues sink and skipped,
+/// and the resolution never fails.
+///
+/// The progress is reported to the events sink as the resolution goes.
 pub async fn resolve(
-    client: reqwest::Client,
+    transport: &impl Transport,
     params: Params,
     issues: impl Sink<ResolutionError>,
-    filter: impl Fn(&Contextualized<Package>) -> bool,
-) -> Vec<Contextualized<Package>> {
+    events: impl Sink<Event>,
+    mut filter: impl FnMut(&Contextualized<Package>) -> bool,
+) -> Result<Vec<Contextualized<Package>>, ResolutionError> {
     let Params {
         manifest_urls,
         repo_urls,
+        cache,
+        mode,
+        strict,
+        retry_policy,
     } = params;
 
     pin_mut!(issues);
+    pin_mut!(events);
+    let mut reporter = Reporter { issues, events };
 
-    let mut manifest_urls: HashSet<String> = HashSet::from_iter(manifest_urls);
+    let loader = Loader {
+        transport,
+        cache: cache.as_ref(),
+        mode,
+        strict,
+        retry_policy: &retry_policy,
+    };
+
+    // The manifest URLs along with the include chains they were found by.
+    let mut manifest_urls: BTreeMap<String, Vec<String>> = manifest_urls
+        .into_iter()
+        .map(|url| (url, Vec::new()))
+        .collect();
 
     for url in repo_urls {
-        let repo: Repo = match load_meta(&client, &url).await {
-            Ok(val) => val,
-            Err(err) => {
-                let _ = issues
-                    .send(ResolutionError {
-                        url,
-                        error: err.to_string(),
-                    })
-                    .await;
+        let origin = Origin {
+            url: &url,
+            document: Document::Repo,
+            include_chain: &[],
+        };
+        let Some(Loaded { value: repo, .. }) =
+            loader.load::<Repo, _, _>(origin, &mut reporter).await?
+        else {
+            continue;
+        };
+        if let Err(kind) = check_schema_version(repo.schema_version) {
+            loader.fail(origin.issue(kind), &mut reporter).await?;
+            continue;
+        }
+        for item in repo.manifest_urls {
+            if !file::may_refer(&url, &item.url) {
+                let kind = ErrorKind::LocalUrl(item.url);
+                loader.fail(origin.issue(kind), &mut reporter).await?;
                 continue;
             }
-        };
-        manifest_urls.extend(repo.manifest_urls.into_iter().map(|item| item.url));
+            manifest_urls
+                .entry(item.url)
+                .or_insert_with(|| vec![url.clone()]);
+        }
     }
 
     let mut packages = Vec::new();
 
-    for url in manifest_urls {
-        let manifest: Manifest = match load_meta(&client, &url).await {
-            Ok(val) => val,
-            Err(err) => {
-                let _ = issues
-                    .send(ResolutionError {
-                        url,
-                        error: err.to_string(),
-                    })
-                    .await;
+    // The manifest URLs the package identifiers were declared by, per
+    // the version and the canonical platform and architecture.
+    let mut ids: HashMap<(PackageId, Option<String>, String, String), String> = HashMap::new();
+
+    for (url, include_chain) in manifest_urls {
+        let origin = Origin {
+            url: &url,
+            document: Document::Manifest,
+            include_chain: &include_chain,
+        };
+        let Some(Loaded {
+            value: manifest,
+            cached_at,
+        }) = loader.load::<Manifest, _, _>(origin, &mut reporter).await?
+        else {
+            continue;
+        };
+
+        let Manifest {
+            schema_version,
+            packages: manifest_packages,
+            mirrors,
+        } = manifest;
+        if let Err(kind) = check_schema_version(schema_version) {
+            loader.fail(origin.issue(kind), &mut reporter).await?;
+            continue;
+        }
+        let mirror_urls: Vec<String> = mirrors.into_iter().map(|mirror| mirror.0).collect();
+
+        let found = manifest_packages.len();
+        let mut filtered_out = 0;
+        for package in manifest_packages {
+            let package = Contextualized {
+                manifest_url: url.clone(),
+                mirror_urls: mirror_urls.clone(),
+                cached_at,
+                value: package,
+            };
+            if !filter(&package) {
+                filtered_out += 1;
                 continue;
             }
+
+            if let Some(id) = &package.value.id {
+                let version = package
+                    .value
+                    .version
+                    .as_ref()
+                    .map(|version| version.0.clone());
+                let platform = humanode_distribution_platform::platform(&package.value.platform.0)
+                    .into_owned();
+                let arch = humanode_distribution_platform::arch(&package.value.arch.0).into_owned();
+                let key = (id.clone(), version.clone(), platform.clone(), arch.clone());
+                match ids.entry(key) {
+                    Entry::Occupied(entry) => {
+                        let kind = ErrorKind::DuplicateId {
+                            id: id.clone(),
+                            version,
+                            platform,
+                            arch,
+                            manifest_url: entry.get().clone(),
+                        };
+                        reporter.issue(origin.issue(kind)).await;
+                        continue;
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(url.clone());
+                    }
+                }
+            }
+
+            packages.push(package);
+        }
+
+        reporter
+            .event(Event::ManifestResolved {
+                url: url.clone(),
+                found,
+                filtered_out,
+                cached_at,
+            })
+            .await;
+    }
+
+    Ok(packages)
+}
+
+/// Where the document being loaded comes from.
+#[derive(Clone, Copy)]
+struct Origin<'a> {
+    /// The document URL.
+    url: &'a str,
+    /// The kind of the document.
+    document: Document,
+    /// The URLs of the documents that led to this one.
+    include_chain: &'a [String],
+}
+
+/// Check that the declared schema version of a document is supported.
+fn check_schema_version(version: Option<u32>) -> Result<(), ErrorKind> {
+    match version {
+        Some(version) if version > humanode_distribution_schema::SCHEMA_VERSION => {
+            Err(ErrorKind::UnsupportedVersion(version))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl Origin<'_> {
+    /// Construct an issue about this document.
+    fn issue(&self, kind: impl Into<ErrorKind>) -> ResolutionError {
+        ResolutionError {
+            url: self.url.to_owned(),
+            document: self.document,
+            include_chain: self.include_chain.to_vec(),
+            kind: kind.into(),
+        }
+    }
+}
+
+/// The sinks to report the issues and the progress to.
+struct Reporter<'a, I, E> {
+    /// The issues sink.
+    issues: std::pin::Pin<&'a mut I>,
+    /// The progress events sink.
+    events: std::pin::Pin<&'a mut E>,
+}
+
+impl<I: Sink<ResolutionError>, E: Sink<Event>> Reporter<'_, I, E> {
+    /// Report an issue.
+    async fn issue(&mut self, issue: ResolutionError) {
+        let _ = self.issues.send(issue).await;
+    }
+
+    /// Report a progress event.
+    async fn event(&mut self, event: Event) {
+        let _ = self.events.send(event).await;
+    }
+}
+
+/// The document loader that takes care of the caching.
+struct Loader<'a, T> {
+    /// The transport.
+    transport: &'a T,
+    /// The cache, if any.
+    cache: Option<&'a Cache>,
+    /// The way to obtain the documents.
+    mode: Mode,
+    /// Whether the load failures are fatal.
+    strict: bool,
+    /// The retry policy.
+    retry_policy: &'a retry::Policy,
+}
+
+impl<Tr: Transport> Loader<'_, Tr> {
+    /// Load the document, reporting the issues and the progress along
+    /// the way.
+    ///
+    /// In the online mode the document is fetched from the network and stored
+    /// in the cache; if that fails the cached copy is used instead.
+    /// In the offline mode only the cached copy is used.
+    /// In the strict mode any failure is returned as an error right away.
+    async fn load<T, I, E>(
+        &self,
+        origin: Origin<'_>,
+        reporter: &mut Reporter<'_, I, E>,
+    ) -> Result<Option<Loaded<T>>, ResolutionError>
+    where
+        T: for<'de> Deserialize<'de>,
+        I: Sink<ResolutionError>,
+        E: Sink<Event>,
+    {
+        if self.mode == Mode::Online {
+            match self.load_live(origin, reporter).await {
+                Ok(value) => {
+                    return Ok(Some(Loaded {
+                        value,
+                        cached_at: None,
+                    }))
+                }
+                Err(err) => self.fail(origin.issue(err), reporter).await?,
+            }
+        }
+
+        let Some(cache) = self.cache else {
+            if self.mode == Mode::Offline {
+                self.fail(origin.issue(ErrorKind::NoCache), reporter)
+                    .await?;
+            }
+            return Ok(None);
         };
 
-        packages.extend(
-            manifest
-                .packages
-                .into_iter()
-                .map(|package| Contextualized {
-                    manifest_url: url.clone(),
-                    value: package,
-                })
-                .filter(&filter),
-        );
+        match load_cached(cache, origin.url).await {
+            Ok(val) => Ok(Some(val)),
+            Err(err) => {
+                self.fail(origin.issue(err), reporter).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Handle a load failure: return it in the strict mode, or report it
+    /// otherwise.
+    async fn fail<I, E>(
+        &self,
+        issue: ResolutionError,
+        reporter: &mut Reporter<'_, I, E>,
+    ) -> Result<(), ResolutionError>
+    where
+        I: Sink<ResolutionError>,
+        E: Sink<Event>,
+    {
+        if self.strict {
+            return Err(issue);
+        }
+        reporter.issue(issue).await;
+        Ok(())
+    }
+
+    /// Fetch the document from the network and store it in the cache.
+    ///
+    /// Cache write failures are reported but do not fail the loading.
+    async fn load_live<T, I, E>(
+        &self,
+        origin: Origin<'_>,
+        reporter: &mut Reporter<'_, I, E>,
+    ) -> Result<T, LoadError>
+    where
+        T: for<'de> Deserialize<'de>,
+        I: Sink<ResolutionError>,
+        E: Sink<Event>,
+    {
+        reporter
+            .event(Event::FetchStarted {
+                url: origin.url.to_owned(),
+                document: origin.document,
+            })
+            .await;
+
+        let started_at = Instant::now();
+        let result = load_meta_bytes(self.transport, self.retry_policy, origin.url).await;
+        let duration = started_at.elapsed();
+
+        let event = match &result {
+            Ok(bytes) => Event::FetchFinished {
+                url: origin.url.to_owned(),
+                document: origin.document,
+                bytes: bytes.len(),
+                duration,
+            },
+            Err(_) => Event::FetchFailed {
+                url: origin.url.to_owned(),
+                document: origin.document,
+                duration,
+            },
+        };
+        reporter.event(event).await;
+
+        let bytes = result?;
+        let value = parse_meta(&bytes)?;
+
+        if let Some(cache) = self.cache {
+            if let Err(err) = cache.store(origin.url, &bytes).await {
+                reporter.issue(origin.issue(ErrorKind::Cache(err))).await;
+            }
+        }
+
+        Ok(value)
     }
+}
 
-    packages
+/// Load the document from the cache.
+async fn load_cached<T>(cache: &Cache, url: &str) -> Result<Loaded<T>, ErrorKind>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let cached = cache.load(url).await.map_err(ErrorKind::Cache)?;
+    let value = parse_meta(cached.data.as_bytes())?;
+    Ok(Loaded {
+        value,
+        cached_at: Some(cached.fetched_at),
+    })
 }
 
 impl<T> AsRef<T> for Contextualized<T> {
@@ -116,3 +562,481 @@ impl<T> AsRef<T> for Contextualized<T> {
         &self.value
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use humanode_distribution_schema::{
+        manifest::{Arch, Platform, Version},
+        test_utils,
+    };
+    use humanode_distribution_transport::memory::Memory;
+
+    use super::*;
+
+    /// A manifest with a package per given platform.
+    fn manifest(platforms: &[&str]) -> String {
+        manifest_of(platforms.iter().map(|platform| Package {
+            platform: Platform((*platform).into()),
+            ..test_utils::package(&format!("Package for {platform}"))
+        }))
+    }
+
+    /// A manifest with the given packages.
+    fn manifest_of(packages: impl IntoIterator<Item = Package>) -> String {
+        serde_yaml_bw::to_string(&Manifest {
+            schema_version: None,
+            packages: packages.into_iter().collect(),
+            mirrors: Vec::new(),
+        })
+        .unwrap()
+    }
+
+    /// The resolver params for the given sources.
+    fn params(repo_urls: &[&str], manifest_urls: &[&str], cache: Option<Cache>) -> Params {
+        Params {
+            manifest_urls: manifest_urls.iter().map(|&url| url.to_owned()).collect(),
+            repo_urls: repo_urls.iter().map(|&url| url.to_owned()).collect(),
+            cache,
+            mode: Mode::Online,
+            strict: false,
+            retry_policy: retry::Policy::no_retries(),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_repos_and_manifests() {
+        let transport = Memory::default()
+            .with(
+                "https://example.com/repo.yml",
+                "manifestUrls:\n  - url: https://example.com/a.yml\n  - url: https://example.com/missing.yml\n",
+            )
+            .with("https://example.com/a.yml", manifest(&["Linux", "Darwin"]))
+            .with("https://example.com/b.yml", manifest(&["Linux"]));
+
+        let mut issues = Vec::new();
+        let mut packages = resolve(
+            &transport,
+            params(
+                &["https://example.com/repo.yml"],
+                &["https://example.com/b.yml"],
+                None,
+            ),
+            &mut issues,
+            futures::sink::drain(),
+            |package| package.value.platform.0 == "Linux",
+        )
+        .await
+        .unwrap();
+        packages.sort_by(|a, b| a.manifest_url.cmp(&b.manifest_url));
+
+        let manifest_urls: Vec<_> = packages.iter().map(|p| p.manifest_url.as_str()).collect();
+        assert_eq!(
+            manifest_urls,
+            ["https://example.com/a.yml", "https://example.com/b.yml"]
+        );
+        assert!(packages.iter().all(|p| p.cached_at.is_none()));
+
+        assert_eq!(issues.len(), 1);
+        let issue = &issues[0];
+        assert_eq!(issue.url, "https://example.com/missing.yml");
+        assert_eq!(issue.document, Document::Manifest);
+        assert_eq!(issue.include_chain, ["https://example.com/repo.yml"]);
+        assert!(matches!(
+            issue.kind,
+            ErrorKind::Status(status) if status == reqwest::StatusCode::NOT_FOUND
+        ));
+        assert_eq!(
+            issue.to_string(),
+            "https://example.com/missing.yml: server error: 404 Not Found"
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_remote_repos_off_local_files() {
+        let repo = "manifestUrls:\n  - url: file:///srv/manifest.yml\n";
+        let transport = Memory::default()
+            .with("https://example.com/repo.yml", repo)
+            .with("file:///srv/repo.yml", repo)
+            .with("file:///srv/manifest.yml", manifest(&["Linux"]));
+
+        let mut issues = Vec::new();
+        let packages = resolve(
+            &transport,
+            params(&["https://example.com/repo.yml"], &[], None),
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert!(packages.is_empty());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].url, "https://example.com/repo.yml");
+        assert!(
+            matches!(issues[0].kind, ErrorKind::LocalUrl(ref url) if url == "file:///srv/manifest.yml"),
+            "{:?}",
+            issues[0]
+        );
+
+        let mut issues = Vec::new();
+        let packages = resolve(
+            &transport,
+            params(&["file:///srv/repo.yml"], &[], None),
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert!(issues.is_empty());
+        assert_eq!(packages.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_unsupported_schema_versions() {
+        let transport = Memory::default()
+            .with(
+                "https://example.com/repo.yml",
+                "schemaVersion: 2\nmanifestUrls:\n  - url: https://example.com/a.yml\n",
+            )
+            .with(
+                "https://example.com/a.yml",
+                format!("schemaVersion: 2\n{}", manifest(&["Linux"])),
+            )
+            .with(
+                "https://example.com/b.yml",
+                format!("schemaVersion: 1\n{}", manifest(&["Linux"])),
+            );
+
+        let mut issues = Vec::new();
+        let packages = resolve(
+            &transport,
+            params(
+                &["https://example.com/repo.yml"],
+                &["https://example.com/a.yml", "https://example.com/b.yml"],
+                None,
+            ),
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].manifest_url, "https://example.com/b.yml");
+
+        let issues: Vec<_> = issues
+            .iter()
+            .map(|issue| (issue.url.as_str(), issue.kind.to_string()))
+            .collect();
+        let message = "unsupported schema version 2, only up to 1 is supported";
+        assert_eq!(
+            issues,
+            [
+                ("https://example.com/repo.yml", message.to_owned()),
+                ("https://example.com/a.yml", message.to_owned()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_parse_error_location() {
+        let url = "https://example.com/repo.yml";
+        let transport = Memory::default().with(url, "manifestUrls:\n  - url: [\n");
+
+        let mut issues = Vec::new();
+        let packages = resolve(
+            &transport,
+            params(&[url], &[], None),
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert!(packages.is_empty());
+
+        assert_eq!(issues.len(), 1);
+        let issue = &issues[0];
+        assert_eq!(issue.document, Document::Repo);
+        assert!(issue.include_chain.is_empty());
+        assert!(
+            matches!(issue.kind, ErrorKind::Parse { line: Some(3), .. }),
+            "{issue:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        let url = "https://example.com/manifest.yml";
+
+        let online = Memory::default().with(url, manifest(&["Linux"]));
+        let mut issues = Vec::new();
+        let packages = resolve(
+            &online,
+            params(&[], &[url], Some(cache.clone())),
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(packages.len(), 1);
+        assert!(packages[0].cached_at.is_none());
+        assert!(issues.is_empty());
+
+        let unreachable = Memory::default();
+        let packages = resolve(
+            &unreachable,
+            params(&[], &[url], Some(cache.clone())),
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(packages.len(), 1);
+        assert!(packages[0].cached_at.is_some());
+        assert_eq!(issues.len(), 1);
+
+        let offline = Memory::default();
+        let packages = resolve(
+            &offline,
+            Params {
+                mode: Mode::Offline,
+                ..params(&[], &[url], Some(cache))
+            },
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(packages.len(), 1);
+        assert!(offline.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn offline_mode_requires_a_cache() {
+        let url = "https://example.com/manifest.yml";
+        let transport = Memory::default().with(url, manifest(&["Linux"]));
+
+        let mut issues = Vec::new();
+        let packages = resolve(
+            &transport,
+            Params {
+                mode: Mode::Offline,
+                ..params(&[], &[url], None)
+            },
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert!(packages.is_empty());
+        assert!(transport.requests().is_empty());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].url, url);
+        assert!(matches!(issues[0].kind, ErrorKind::NoCache));
+    }
+
+    #[tokio::test]
+    async fn strict_mode_fails_on_first_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        let url = "https://example.com/manifest.yml";
+        let missing_url = "https://example.com/missing.yml";
+
+        let online = Memory::default().with(url, manifest(&["Linux"]));
+        let mut issues = Vec::new();
+        resolve(
+            &online,
+            params(&[], &[url], Some(cache.clone())),
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+
+        // A stale cached copy does not satisfy the strict mode.
+        let unreachable = Memory::default();
+        let error = resolve(
+            &unreachable,
+            Params {
+                strict: true,
+                ..params(&[], &[url], Some(cache))
+            },
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.url, url);
+        assert!(issues.is_empty());
+
+        let partial = Memory::default().with(url, manifest(&["Linux"]));
+        let error = resolve(
+            &partial,
+            Params {
+                strict: true,
+                ..params(&[], &[url, missing_url], None)
+            },
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.url, missing_url);
+        assert!(issues.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_duplicate_ids() {
+        let package = |id: &str, version: &str, arch: &str| Package {
+            id: Some(PackageId(id.into())),
+            version: Some(Version(version.into())),
+            arch: Arch(arch.into()),
+            ..test_utils::package(&format!("Package {id}"))
+        };
+        let transport = Memory::default()
+            .with(
+                "https://example.com/a.yml",
+                manifest_of([
+                    package("peer", "1.0", "x86_64"),
+                    package("peer", "1.0", "arm64"),
+                ]),
+            )
+            .with(
+                "https://example.com/b.yml",
+                manifest_of([
+                    package("peer", "1.0", "amd64"),
+                    package("peer", "1.1", "x86_64"),
+                    package("bench", "1.0", "x86_64"),
+                ]),
+            );
+        let urls = ["https://example.com/b.yml", "https://example.com/a.yml"];
+
+        let mut issues = Vec::new();
+        let packages = resolve(
+            &transport,
+            params(&[], &urls, None),
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+
+        let packages: Vec<_> = packages
+            .iter()
+            .map(|package| {
+                (
+                    package.manifest_url.as_str(),
+                    package.value.id.as_ref().unwrap().0.as_str(),
+                    package.value.version.as_ref().unwrap().0.as_str(),
+                    package.value.arch.0.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            packages,
+            [
+                ("https://example.com/a.yml", "peer", "1.0", "x86_64"),
+                ("https://example.com/a.yml", "peer", "1.0", "arm64"),
+                ("https://example.com/b.yml", "peer", "1.1", "x86_64"),
+                ("https://example.com/b.yml", "bench", "1.0", "x86_64"),
+            ]
+        );
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].url, "https://example.com/b.yml");
+        assert_eq!(
+            issues[0].kind.to_string(),
+            "package id peer version 1.0 for Linux x86_64 \
+             is already declared by https://example.com/a.yml"
+        );
+
+        let mut issues = Vec::new();
+        let strict = resolve(
+            &transport,
+            Params {
+                strict: true,
+                ..params(&[], &urls, None)
+            },
+            &mut issues,
+            futures::sink::drain(),
+            |_| true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(strict.len(), packages.len());
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(issues[0].kind, ErrorKind::DuplicateId { .. }));
+    }
+
+    #[tokio::test]
+    async fn reports_progress() {
+        let repo_url = "https://example.com/repo.yml";
+        let manifest_url = "https://example.com/manifest.yml";
+        let transport = Memory::default()
+            .with(
+                repo_url,
+                format!("manifestUrls:\n  - url: {manifest_url}\n"),
+            )
+            .with(manifest_url, manifest(&["Linux", "Darwin", "Windows"]));
+
+        let mut events = Vec::new();
+        let packages = resolve(
+            &transport,
+            params(&[repo_url], &[], None),
+            futures::sink::drain(),
+            &mut events,
+            |package| package.value.platform.0 == "Linux",
+        )
+        .await
+        .unwrap();
+        assert_eq!(packages.len(), 1);
+
+        let events: Vec<String> = events
+            .into_iter()
+            .map(|event| match event {
+                Event::FetchStarted { url, document } => format!("started {document} {url}"),
+                Event::FetchFinished {
+                    url,
+                    document,
+                    bytes,
+                    ..
+                } => {
+                    assert!(bytes > 0);
+                    format!("finished {document} {url}")
+                }
+                Event::FetchFailed { url, document, .. } => format!("failed {document} {url}"),
+                Event::ManifestResolved {
+                    url,
+                    found,
+                    filtered_out,
+                    cached_at,
+                } => {
+                    assert!(cached_at.is_none());
+                    format!("resolved {url}: {found} found, {filtered_out} filtered out")
+                }
+            })
+            .collect();
+        assert_eq!(
+            events,
+            [
+                format!("started repo {repo_url}"),
+                format!("finished repo {repo_url}"),
+                format!("started manifest {manifest_url}"),
+                format!("finished manifest {manifest_url}"),
+                format!("resolved {manifest_url}: 3 found, 2 filtered out"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! The version comparison.
+
+use std::cmp::Ordering;
+
+/// Compare the dotted versions, like `2.35` or `6.1.0-18-amd64`.
+///
+/// The components are compared numerically by their leading digits, so
+/// the suffixes are ignored, and the missing components count as zeros.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let a = components(a);
+    let b = components(b);
+    let len = a.len().max(b.len());
+    let padded = |components: &[u64]| {
+        let mut components = components.to_vec();
+        components.resize(len, 0);
+        components
+    };
+    padded(&a).cmp(&padded(&b))
+}
+
+/// Check whether the version is the same as or newer than the minimum one.
+pub fn is_at_least(version: &str, min: &str) -> bool {
+    compare(version, min) != Ordering::Less
+}
+
+/// A parsed version requirement, like `>=1.2, <2`.
+///
+/// The version must satisfy all of the comparisons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    /// The comparisons.
+    comparisons: Vec<(Op, String)>,
+}
+
+/// A version comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// Same as the version.
+    Eq,
+    /// Newer than the version.
+    Gt,
+    /// Same as or newer than the version.
+    Ge,
+    /// Older than the version.
+    Lt,
+    /// Same as or older than the version.
+    Le,
+}
+
+impl Op {
+    /// The operators along with their notations; the longer ones go first.
+    const ALL: [(&'static str, Self); 5] = [
+        (">=", Self::Ge),
+        ("<=", Self::Le),
+        (">", Self::Gt),
+        ("<", Self::Lt),
+        ("=", Self::Eq),
+    ];
+}
+
+/// An error that can occur when parsing a version requirement.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RequirementError {
+    /// A comparison has no version to compare with.
+    #[error("missing version in {0:?}")]
+    MissingVersion(String),
+    /// The version does not start with a digit.
+    #[error("invalid version {0:?}")]
+    InvalidVersion(String),
+}
+
+impl std::str::FromStr for Requirement {
+    type Err = RequirementError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let comparisons = s
+            .split(',')
+            .map(|comparison| {
+                let comparison = comparison.trim();
+                let (op, version) = Op::ALL
+                    .iter()
+                    .find_map(|(notation, op)| {
+                        comparison
+                            .strip_prefix(notation)
+                            .map(|version| (*op, version.trim()))
+                    })
+                    .unwrap_or((Op::Eq, comparison));
+                if version.is_empty() {
+                    return Err(RequirementError::MissingVersion(comparison.to_owned()));
+                }
+                if !version.starts_with(|c: char| c.is_ascii_digit()) {
+                    return Err(RequirementError::InvalidVersion(version.to_owned()));
+                }
+                Ok((op, version.to_owned()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { comparisons })
+    }
+}
+
+impl Requirement {
+    /// Check whether the version satisfies the requirement.
+    pub fn matches(&self, version: &str) -> bool {
+        self.comparisons.iter().all(|(op, required)| {
+            let ordering = compare(version, required);
+            match op {
+                Op::Eq => ordering == Ordering::Equal,
+                Op::Gt => ordering == Ordering::Greater,
+                Op::Ge => ordering != Ordering::Less,
+                Op::Lt => ordering == Ordering::Less,
+                Op::Le => ordering != Ordering::Greater,
+            }
+        })
+    }
+}
+
+/// Get the numeric components of the version.
+fn components(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .split('.')
+        .map(|component| {
+            let digits = component
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(component, |end| &component[..end]);
+            digits.parse().unwrap_or(0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions() {
+        let cases = [
+            ("2.35", "2.35", Ordering::Equal),
+            ("2.35", "2.31", Ordering::Greater),
+            ("2.9", "2.10", Ordering::Less),
+            ("14", "14.0.0", Ordering::Equal),
+            ("14.2.1", "14.2", Ordering::Greater),
+            ("6.1.0-18-amd64", "6.1", Ordering::Equal),
+            ("5.15.0-91-generic", "6.1", Ordering::Less),
+            ("23.1.0", "22.6.0", Ordering::Greater),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(compare(a, b), expected, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn matches_requirements() {
+        let requirement: Requirement = ">=1.2, <2".parse().unwrap();
+        assert!(requirement.matches("1.2"));
+        assert!(requirement.matches("1.10.3"));
+        assert!(!requirement.matches("1.1.9"));
+        assert!(!requirement.matches("2.0"));
+
+        let requirement: Requirement = "0.5".parse().unwrap();
+        assert!(requirement.matches("0.5.0"));
+        assert!(!requirement.matches("0.5.1"));
+
+        assert_eq!(
+            ">=".parse::<Requirement>(),
+            Err(RequirementError::MissingVersion(">=".into()))
+        );
+        assert_eq!(
+            "~1.2".parse::<Requirement>(),
+            Err(RequirementError::InvalidVersion("~1.2".into()))
+        );
+    }
+}
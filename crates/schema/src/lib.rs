@@ -1,5 +1,12 @@
 //! Humanode Distribution Schema.
 
+/// The newest version of the documents schema this crate can read.
+///
+/// The repos and the manifests without a declared version are of
+/// the version 1.
+pub const SCHEMA_VERSION: u32 = 1;
+
 pub mod manifest;
 pub mod repo;
-mod test_utils;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
@@ -3,21 +3,53 @@
 use serde::{Deserialize, Serialize};
 
 /// A single manifest.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Manifest {
+    /// The version of the schema the manifest is written in.
+    #[serde(
+        rename = "schemaVersion",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub schema_version: Option<u32>,
     /// Packages provided by this manifest.
     #[serde(rename = "binaries")]
     pub packages: Vec<Package>,
+    /// The alternate base URLs to load the files from if loading them
+    /// relative to the manifest URL fails, tried in order.
+    ///
+    /// Each mirror must serve the files at the same sub URLs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<Url>,
 }
 
 /// A single package.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Package {
     /// The platform this package is intended for.
     pub platform: Platform,
     /// The architecture this package is intended for.
     pub arch: Arch,
+    /// The other architectures this package runs on natively, for instance
+    /// when it ships universal binaries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_arches: Vec<Arch>,
+
+    /// The stable machine identifier of the package, like `humanode-peer`.
+    ///
+    /// Unlike the display name it does not change between the releases, and
+    /// is shared by the builds of the package for the different platforms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<PackageId>,
+
+    /// The version of the package, like `0.5.1`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+
+    /// The release channel of the package, like `stable` or `beta`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<Channel>,
 
     /// The name to use when displaying the package.
     pub display_name: String,
@@ -39,6 +71,18 @@ pub struct Package {
 
     /// Files included in this package.
     pub files: Vec<File>,
+
+    /// The system requirements of this package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requirements: Option<Requirements>,
+
+    /// The hardware resources this package needs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<Resources>,
+
+    /// The other packages this package needs, installed along with it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<Dependency>,
 }
 
 /// The platform code.
@@ -49,7 +93,7 @@ pub struct Package {
 /// - Darwin
 /// - Linux
 /// - Windows
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Platform(pub String);
 
 /// The architecture code.
@@ -59,29 +103,163 @@ pub struct Platform(pub String);
 /// Sample values are:
 /// - x86_64
 /// - arm64
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Arch(pub String);
 
+/// The release channel.
+///
+/// Sample values are:
+/// - stable
+/// - beta
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Channel(pub String);
+
+/// The package identifier.
+///
+/// Sample values are:
+/// - humanode-peer
+/// - humanode-peer-testnet
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct PackageId(pub String);
+
+impl std::fmt::Display for PackageId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The system requirements of a package.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Requirements {
+    /// The minimum OS version: the product version on macOS, and the version
+    /// of the distribution on Linux.
+    ///
+    /// The version is only checked on the systems of the `distribution`, so
+    /// it is not checked on Linux unless the distribution is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_os_version: Option<Version>,
+    /// The Linux distribution the minimum OS version refers to, as the
+    /// `os-release` identifier, like `ubuntu`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<String>,
+    /// The minimum kernel release.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_kernel_version: Option<Version>,
+    /// The C library the package is built against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub libc: Option<LibcRequirement>,
+    /// The CPU features the package is built for, like `avx2`, or
+    /// the microarchitecture levels, like `x86-64-v3`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_features: Vec<String>,
+}
+
+/// The hardware resources a package needs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Resources {
+    /// The minimum physical memory, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_memory: Option<u64>,
+    /// The minimum number of CPU cores.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_cpu_cores: Option<u32>,
+    /// The disk space the installed package takes, in bytes.
+    ///
+    /// If not set, the sum of the file sizes is used when all of them
+    /// are known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_space: Option<u64>,
+}
+
+/// A dependency on another package.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependency {
+    /// The identifier of the package.
+    pub id: PackageId,
+    /// The versions of the package that fit; any version fits if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<VersionRequirement>,
+    /// The sub path of the installation directory to install the package
+    /// to; the identifier is used if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination_sub_path: Option<LocalPath>,
+}
+
+/// The version requirement: the comma-separated comparisons with
+/// the versions, like `>=1.2, <2`.
+///
+/// The operators are `=`, `>`, `>=`, `<` and `<=`; a version without
+/// an operator must be matched exactly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionRequirement(pub String);
+
+/// The C library requirement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibcRequirement {
+    /// The C library flavour.
+    pub kind: LibcKind,
+    /// The minimum C library version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<Version>,
+}
+
+/// The C library flavour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LibcKind {
+    /// The GNU C library.
+    Glibc,
+    /// The musl C library.
+    Musl,
+}
+
+impl std::fmt::Display for LibcKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Glibc => "glibc",
+            Self::Musl => "musl",
+        })
+    }
+}
+
+/// A dotted version, like `2.35`.
+///
+/// The components are compared numerically.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Version(pub String);
+
 /// Relative path in the context of the file system of the distribution.
 ///
 /// Must be evaludated relative to the directory of the distribution root, or
 /// in the context where the distribution root is the process' current directory.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalPath(pub String);
 
 /// The URL.
 ///
 /// Have to evaluated against the Manifest URL using the Base URL algorithm,
 /// see <https://developer.mozilla.org/en-US/docs/Web/API/URL/URL>.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Url(pub String);
 
 /// The hexadecimal representation of a SHA-256 sum.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sha256(pub String);
 
+/// The hexadecimal representation of a SHA-512 sum.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sha512(pub String);
+
+/// The hexadecimal representation of a BLAKE3 hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blake3(pub String);
+
 /// A single file description.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct File {
     /// The sub URL where to look for the asset.
@@ -89,7 +267,20 @@ pub struct File {
     /// The destination sub path to place the file at.
     pub destination_sub_path: LocalPath,
     /// The SHA-256 sum of the file.
-    pub sha256: Sha256,
+    ///
+    /// At least one of the digests must be set; all of the set ones
+    /// are verified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<Sha256>,
+    /// The SHA-512 sum of the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha512: Option<Sha512>,
+    /// The BLAKE3 hash of the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blake3: Option<Blake3>,
+    /// The size of the file, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
 }
 
 #[cfg(test)]
@@ -6,6 +6,9 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Repo {
+    /// The version of the schema the repo is written in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
     /// A list of URLs each serving a manifest.
     pub manifest_urls: Vec<ManifestUrl>,
 }
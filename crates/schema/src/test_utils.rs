@@ -1,10 +1,12 @@
 //! Test utils.
 
-#![cfg(test)]
-
+#[cfg(test)]
 use std::path::{Path, PathBuf};
 
+use crate::manifest::{Arch, LocalPath, Package, Platform};
+
 /// Read a test asset.
+#[cfg(test)]
 pub fn read_test_asset<P: AsRef<Path>>(path: P) -> Vec<u8> {
     let dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap();
     let path = {
@@ -15,3 +17,30 @@ pub fn read_test_asset<P: AsRef<Path>>(path: P) -> Vec<u8> {
     };
     std::fs::read(path).unwrap()
 }
+
+/// A package with the given display name for the tests: a Linux x86_64
+/// build without files, requirements or dependencies.
+///
+/// Override the fields with the struct update syntax, like
+/// `Package { arch: Arch("arm64".into()), ..package("Test") }`.
+pub fn package(display_name: &str) -> Package {
+    Package {
+        platform: Platform("Linux".into()),
+        arch: Arch("x86_64".into()),
+        additional_arches: Vec::new(),
+        id: None,
+        version: None,
+        channel: None,
+        display_name: display_name.into(),
+        description: "Test package.".into(),
+        icon: "icon.png".into(),
+        executable_path: LocalPath("bin/peer".into()),
+        chainspec_path: LocalPath("chainspec.json".into()),
+        ngrok_path: LocalPath("bin/ngrok".into()),
+        humanode_websocket_tunnel_client_path: LocalPath("bin/tunnel".into()),
+        files: Vec::new(),
+        requirements: None,
+        resources: None,
+        dependencies: Vec::new(),
+    }
+}
@@ -0,0 +1,107 @@
+//! The local file system sources, addressed by the `file://` URLs.
+
+use std::path::{Path, PathBuf};
+
+/// An error that can happen when converting the `file://` URL to a path.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid file URL {0}")]
+pub struct InvalidFileUrl(pub String);
+
+/// Get the local path the URL points to.
+///
+/// Returns `None` if the URL does not have the `file://` scheme.
+pub fn path(url: &str) -> Option<Result<PathBuf, InvalidFileUrl>> {
+    let parsed = url::Url::parse(url).ok()?;
+    if parsed.scheme() != "file" {
+        return None;
+    }
+    Some(
+        parsed
+            .to_file_path()
+            .map_err(|()| InvalidFileUrl(url.to_owned())),
+    )
+}
+
+/// Check whether the document loaded from the referrer URL may refer to
+/// the URL.
+///
+/// The remote documents must not reach into the local file system, so only
+/// the documents loaded from the `file://` URLs may refer to the `file://`
+/// URLs; the sources given by the user are not referred to by anything and
+/// are not restricted.
+pub fn may_refer(referrer: &str, url: &str) -> bool {
+    path(url).is_none() || path(referrer).is_some()
+}
+
+/// Turn a source given by the user into a URL.
+///
+/// The values that are already URLs are returned as is, while the plain
+/// file system paths are made absolute and turned into the `file://` URLs.
+pub fn source_url(value: &str) -> Result<String, std::io::Error> {
+    // Single letter schemes are Windows drive letters rather than URLs.
+    if let Ok(url) = url::Url::parse(value) {
+        if url.scheme().len() > 1 {
+            return Ok(value.to_owned());
+        }
+    }
+
+    let path = std::path::absolute(Path::new(value))?;
+    let url = url::Url::from_file_path(&path).map_err(|()| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("unable to represent {} as a URL", path.display()),
+        )
+    })?;
+    Ok(url.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_are_kept() {
+        for url in [
+            "https://example.com/repo.yml",
+            "file:///srv/repo.yml",
+            "http://localhost:8080/manifest.json",
+        ] {
+            assert_eq!(source_url(url).unwrap(), url);
+        }
+    }
+
+    #[test]
+    fn paths_become_file_urls() {
+        let url = source_url("testdata/repo.yml").unwrap();
+        let expected = std::env::current_dir().unwrap().join("testdata/repo.yml");
+        assert!(url.starts_with("file://"), "{url}");
+        assert_eq!(path(&url).unwrap().unwrap(), expected);
+    }
+
+    #[test]
+    fn only_local_documents_refer_to_local_files() {
+        let cases = [
+            ("file:///srv/repo.yml", "file:///srv/manifest.yml", true),
+            (
+                "file:///srv/repo.yml",
+                "https://example.com/manifest.yml",
+                true,
+            ),
+            (
+                "https://example.com/repo.yml",
+                "https://example.com/manifest.yml",
+                true,
+            ),
+            ("https://example.com/repo.yml", "manifest.yml", true),
+            ("https://example.com/repo.yml", "file:///etc/passwd", false),
+        ];
+        for (referrer, url, expected) in cases {
+            assert_eq!(may_refer(referrer, url), expected, "{referrer} {url}");
+        }
+    }
+
+    #[test]
+    fn other_schemes_have_no_path() {
+        assert!(path("https://example.com/repo.yml").is_none());
+    }
+}
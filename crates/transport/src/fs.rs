@@ -0,0 +1,31 @@
+//! The local file system transport.
+
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::{file, ByteStream, Error, Transport};
+
+/// The transport that reads the `file://` URLs from the local file system.
+#[derive(Debug, Clone, Default)]
+pub struct Fs;
+
+/// Get the local path for the URL, failing for the non-`file://` URLs.
+fn path(url: &str) -> Result<std::path::PathBuf, Error> {
+    file::path(url)
+        .ok_or_else(|| Error::UnsupportedScheme(url.to_owned()))?
+        .map_err(Error::InvalidFileUrl)
+}
+
+impl Transport for Fs {
+    async fn fetch(&self, url: &str) -> Result<Bytes, Error> {
+        let data = tokio::fs::read(path(url)?).await.map_err(Error::Io)?;
+        Ok(data.into())
+    }
+
+    async fn stream(&self, url: &str) -> Result<ByteStream, Error> {
+        let file = tokio::fs::File::open(path(url)?).await.map_err(Error::Io)?;
+        Ok(tokio_util::io::ReaderStream::new(file)
+            .map_err(Error::Io)
+            .boxed())
+    }
+}
@@ -0,0 +1,59 @@
+//! The HTTP transport.
+
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::{retry, ByteStream, Error, Transport};
+
+/// The HTTP(S) transport backed by `reqwest`.
+#[derive(Debug, Clone, Default)]
+pub struct Http {
+    /// HTTP client.
+    pub client: reqwest::Client,
+}
+
+impl Http {
+    /// Create a new transport with the given client.
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Send a GET request and check the response status.
+    async fn get(
+        &self,
+        url: &str,
+        accept: Option<&'static str>,
+    ) -> Result<reqwest::Response, Error> {
+        let mut req = self.client.get(url);
+        if let Some(accept) = accept {
+            req = req.header(reqwest::header::ACCEPT, accept);
+        }
+        let req = req.build().map_err(Error::Reqwest)?;
+
+        let res = self.client.execute(req).await.map_err(Error::Reqwest)?;
+
+        let status = res.status();
+        if !status.is_success() {
+            return Err(Error::Server {
+                status,
+                retry_after: retry::retry_after(res.headers()),
+            });
+        }
+
+        Ok(res)
+    }
+}
+
+impl Transport for Http {
+    async fn fetch(&self, url: &str) -> Result<Bytes, Error> {
+        let res = self
+            .get(url, Some("application/json,application/x-yaml,text/yaml"))
+            .await?;
+        res.bytes().await.map_err(Error::Reqwest)
+    }
+
+    async fn stream(&self, url: &str) -> Result<ByteStream, Error> {
+        let res = self.get(url, None).await?;
+        Ok(res.bytes_stream().map_err(Error::Reqwest).boxed())
+    }
+}
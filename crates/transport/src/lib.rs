@@ -0,0 +1,75 @@
+//! The transport machinery shared by the resolver and the installer.
+
+use std::{future::Future, time::Duration};
+
+use bytes::Bytes;
+use futures::stream::BoxStream;
+
+pub mod file;
+pub mod fs;
+pub mod http;
+pub mod memory;
+pub mod retry;
+pub mod standard;
+
+/// An error that can happen when transferring the data.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A failure from `reqwest`.
+    #[error("reqwest error: {0}")]
+    Reqwest(#[source] reqwest::Error),
+    /// The server returned a bad status code.
+    #[error("server error: {status}")]
+    Server {
+        /// The status code.
+        status: reqwest::StatusCode,
+        /// The delay the server asked to wait before retrying, if any.
+        retry_after: Option<Duration>,
+    },
+    /// The `file://` URL does not point to a valid path.
+    #[error(transparent)]
+    InvalidFileUrl(file::InvalidFileUrl),
+    /// Reading the local file failed.
+    #[error("file read error: {0}")]
+    Io(#[source] std::io::Error),
+    /// The transport does not handle the scheme of the URL.
+    #[error("unsupported URL scheme: {0}")]
+    UnsupportedScheme(String),
+}
+
+impl retry::Classify for Error {
+    fn classify(&self) -> retry::Verdict {
+        match self {
+            Self::Reqwest(error) => retry::classify_reqwest(error),
+            Self::Server {
+                status,
+                retry_after,
+            } => retry::classify_status(*status, *retry_after),
+            Self::InvalidFileUrl(_) | Self::Io(_) | Self::UnsupportedScheme(_) => {
+                retry::Verdict::Fatal
+            }
+        }
+    }
+}
+
+/// A stream of the artifact data chunks.
+pub type ByteStream = BoxStream<'static, Result<Bytes, Error>>;
+
+/// The way to get the data at the given URLs.
+pub trait Transport: Send + Sync {
+    /// Fetch the whole metadata document at the given URL.
+    fn fetch(&self, url: &str) -> impl Future<Output = Result<Bytes, Error>> + Send;
+
+    /// Start streaming the artifact at the given URL.
+    fn stream(&self, url: &str) -> impl Future<Output = Result<ByteStream, Error>> + Send;
+}
+
+impl<T: Transport> Transport for &T {
+    fn fetch(&self, url: &str) -> impl Future<Output = Result<Bytes, Error>> + Send {
+        (**self).fetch(url)
+    }
+
+    fn stream(&self, url: &str) -> impl Future<Output = Result<ByteStream, Error>> + Send {
+        (**self).stream(url)
+    }
+}
@@ -0,0 +1,56 @@
+//! The in-memory transport.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use futures::StreamExt;
+
+use crate::{ByteStream, Error, Transport};
+
+/// The transport that serves the predefined data from memory, and responds
+/// with Not Found to everything else.
+///
+/// Useful for testing, as it records the URLs that were requested.
+#[derive(Debug, Clone, Default)]
+pub struct Memory {
+    /// The data to serve, by URL.
+    pub data: HashMap<String, Bytes>,
+    /// The URLs that were requested, in order.
+    pub requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl Memory {
+    /// Add the data to serve at the given URL.
+    pub fn with(mut self, url: impl Into<String>, data: impl Into<Bytes>) -> Self {
+        self.data.insert(url.into(), data.into());
+        self
+    }
+
+    /// The URLs requested so far.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Look up the data and record the request.
+    fn get(&self, url: &str) -> Result<Bytes, Error> {
+        self.requests.lock().unwrap().push(url.to_owned());
+        self.data.get(url).cloned().ok_or(Error::Server {
+            status: reqwest::StatusCode::NOT_FOUND,
+            retry_after: None,
+        })
+    }
+}
+
+impl Transport for Memory {
+    async fn fetch(&self, url: &str) -> Result<Bytes, Error> {
+        self.get(url)
+    }
+
+    async fn stream(&self, url: &str) -> Result<ByteStream, Error> {
+        let data = self.get(url)?;
+        Ok(futures::stream::once(async move { Ok(data) }).boxed())
+    }
+}
@@ -0,0 +1,283 @@
+//! The retry logic for the transient failures.
+
+use std::{future::Future, time::Duration};
+
+/// The retry policy.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry; doubles with every next retry.
+    pub initial_backoff: Duration,
+    /// The upper bound for the delay between the attempts, including
+    /// the delays requested by the servers.
+    pub max_backoff: Duration,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Policy {
+    /// A policy that never retries.
+    pub fn no_retries() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// The delay to wait before the given retry, starting from zero.
+    ///
+    /// The delay grows exponentially and has a random jitter applied to
+    /// avoid synchronized retries from multiple clients.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        // Equal jitter: keep half of the delay, randomize the other half.
+        let half = exp / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+}
+
+/// The decision on whether to retry after a failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The failure is permanent, do not retry.
+    Fatal,
+    /// The failure is transient, retry after the backoff.
+    Retry,
+    /// The failure is transient, retry after the delay requested by the server.
+    RetryAfter(Duration),
+}
+
+/// The errors that can tell whether they are transient.
+pub trait Classify {
+    /// Decide whether the operation that failed with this error should be
+    /// retried.
+    fn classify(&self) -> Verdict;
+}
+
+/// Classify a `reqwest` error.
+///
+/// Connection failures, timeouts and interrupted transfers are transient.
+pub fn classify_reqwest(error: &reqwest::Error) -> Verdict {
+    if error.is_connect() || error.is_timeout() || error.is_request() || error.is_body() {
+        Verdict::Retry
+    } else {
+        Verdict::Fatal
+    }
+}
+
+/// Classify a non-success response by its status code and the delay from
+/// its `Retry-After` header.
+///
+/// Too Many Requests and server errors are transient, and the requested delay
+/// is honored if present, up to the maximum backoff of the policy.
+pub fn classify_status(status: reqwest::StatusCode, retry_after: Option<Duration>) -> Verdict {
+    if status != reqwest::StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+        return Verdict::Fatal;
+    }
+    match retry_after {
+        Some(delay) => Verdict::RetryAfter(delay),
+        None => Verdict::Retry,
+    }
+}
+
+/// Parse the `Retry-After` header, either in the delay seconds or
+/// the HTTP date form.
+pub fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(std::time::SystemTime::now())
+            .unwrap_or_default(),
+    )
+}
+
+/// Run the operation, retrying it according to the policy while it fails
+/// with transient errors.
+///
+/// The last error is returned if all of the attempts fail.
+pub async fn retry<T, E, F, Fut>(policy: &Policy, mut op: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Classify,
+{
+    let mut retry = 0;
+    loop {
+        let error = match op().await {
+            Ok(val) => return Ok(val),
+            Err(error) => error,
+        };
+
+        if retry + 1 >= policy.max_attempts {
+            return Err(error);
+        }
+
+        let delay = match error.classify() {
+            Verdict::Fatal => return Err(error),
+            Verdict::Retry => policy.backoff(retry),
+            // Do not let a server stall the retries indefinitely.
+            Verdict::RetryAfter(delay) => delay.min(policy.max_backoff),
+        };
+
+        tokio::time::sleep(delay).await;
+        retry += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::{http::Http, Error, Transport};
+
+    /// A fast policy for the tests.
+    fn policy() -> Policy {
+        Policy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(10),
+        }
+    }
+
+    /// Fetch the URL with the given policy.
+    async fn fetch(policy: &Policy, url: &str) -> Result<String, Error> {
+        let transport = Http::default();
+        let data = retry(policy, || transport.fetch(url)).await?;
+        Ok(String::from_utf8(data.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/file"))
+            .respond_with(ResponseTemplate::new(502))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/file"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let body = fetch(&policy(), &format!("{}/file", server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(body, "ok");
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let error = fetch(&policy(), &server.uri()).await.unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Server { status, .. } if status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+        ));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = fetch(&policy(), &server.uri()).await.unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Server { status, .. } if status == reqwest::StatusCode::NOT_FOUND
+        ));
+    }
+
+    #[tokio::test]
+    async fn honors_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let policy = Policy {
+            // Would time the test out if the header was ignored.
+            initial_backoff: Duration::from_secs(3600),
+            max_backoff: Duration::from_secs(3600),
+            ..policy()
+        };
+        let body = fetch(&policy, &server.uri()).await.unwrap();
+        assert_eq!(body, "ok");
+    }
+
+    #[tokio::test]
+    async fn bounds_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "3600"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let body = tokio::time::timeout(Duration::from_secs(10), fetch(&policy(), &server.uri()))
+            .await
+            .expect("the requested delay is not bounded")
+            .unwrap();
+        assert_eq!(body, "ok");
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        let policy = Policy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        for retry in 0..10 {
+            let delay = policy.backoff(retry);
+            let exp = (policy.initial_backoff * 2u32.pow(retry)).min(policy.max_backoff);
+            assert!(delay >= exp / 2 && delay <= exp, "{retry}: {delay:?}");
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! The standard transport.
+
+use bytes::Bytes;
+
+use crate::{file, fs::Fs, http::Http, ByteStream, Error, Transport};
+
+/// The transport that serves the `file://` URLs from the local file system
+/// and everything else over HTTP(S).
+#[derive(Debug, Clone, Default)]
+pub struct Standard {
+    /// The HTTP transport.
+    pub http: Http,
+    /// The file system transport.
+    pub fs: Fs,
+}
+
+impl Standard {
+    /// Create a new transport with the given HTTP client.
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            http: Http::new(client),
+            fs: Fs,
+        }
+    }
+}
+
+impl Transport for Standard {
+    async fn fetch(&self, url: &str) -> Result<Bytes, Error> {
+        if file::path(url).is_some() {
+            return self.fs.fetch(url).await;
+        }
+        self.http.fetch(url).await
+    }
+
+    async fn stream(&self, url: &str) -> Result<ByteStream, Error> {
+        if file::path(url).is_some() {
+            return self.fs.stream(url).await;
+        }
+        self.http.stream(url).await
+    }
+}